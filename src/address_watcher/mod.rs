@@ -0,0 +1,80 @@
+//! Notifications of the local address changes, to check the public IP addresses without waiting for the next poll
+
+use std::io;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[cfg(target_os = "linux")]
+mod netlink_socket;
+
+/// Watches the global addresses and the default routes of the network interfaces.
+/// Only Linux is supported, through rtnetlink notifications.
+pub struct AddressWatcher {
+    #[cfg(target_os = "linux")]
+    netlink_socket: netlink_socket::NetlinkSocket,
+    debounce: Duration,
+    /// Time of the last notification not reported yet
+    last_change: Option<Instant>,
+}
+
+impl AddressWatcher {
+    /// Subscribe to the address and route notifications
+    /// # Arguments
+    /// * `debounce` - Quiet time after the last notification before a change is reported,
+    ///   so that a burst of notifications (e.g. an IPv6 prefix change with its duplicate address detection) is reported once
+    /// # Returns
+    /// * `Err(io::Error)` - If the notifications can't be received, `io::ErrorKind::Unsupported` if the OS isn't supported
+    #[cfg(target_os = "linux")]
+    pub fn new(debounce: Duration) -> io::Result<Self> {
+        Ok(Self {
+            netlink_socket: netlink_socket::NetlinkSocket::new()?,
+            debounce,
+            last_change: None,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new(_debounce: Duration) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "address changes can only be watched on Linux",
+        ))
+    }
+
+    /// Wait until the addresses or the default routes changed, and no notification was received for the debounce time.
+    /// Notifications already received are kept if the returned future is dropped, so it can be used in `tokio::select!`.
+    pub async fn changed(&mut self) -> io::Result<()> {
+        loop {
+            match self.last_change {
+                None => {
+                    self.next_change().await?;
+                    self.last_change = Some(Instant::now());
+                }
+                Some(last_change) => {
+                    match tokio::time::timeout_at(last_change + self.debounce, self.next_change())
+                        .await
+                    {
+                        Ok(change) => {
+                            change?;
+                            self.last_change = Some(Instant::now());
+                        }
+                        Err(_) => {
+                            self.last_change = None;
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn next_change(&mut self) -> io::Result<()> {
+        self.netlink_socket.next_change().await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn next_change(&mut self) -> io::Result<()> {
+        std::future::pending().await
+    }
+}
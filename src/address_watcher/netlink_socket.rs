@@ -0,0 +1,162 @@
+//! rtnetlink socket subscribed to the address and route notifications
+
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
+
+/// Large enough for the notifications sent at once by the kernel
+const RECEIVE_BUFFER_SIZE: usize = 16 * 1024;
+/// Size of `nlmsghdr`, already aligned on 4 bytes
+const NLMSG_HDRLEN: usize = 16;
+
+pub(crate) struct NetlinkSocket {
+    fd: AsyncFd<OwnedFd>,
+    buffer: Vec<u8>,
+}
+
+impl NetlinkSocket {
+    pub(crate) fn new() -> io::Result<Self> {
+        // SAFETY: plain system calls, the file descriptor is owned as soon as it is created
+        let fd = unsafe {
+            let raw_fd = libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            );
+            if raw_fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = OwnedFd::from_raw_fd(raw_fd);
+
+            let mut address: libc::sockaddr_nl = mem::zeroed();
+            address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            address.nl_groups = (libc::RTMGRP_IPV4_IFADDR
+                | libc::RTMGRP_IPV6_IFADDR
+                | libc::RTMGRP_IPV4_ROUTE
+                | libc::RTMGRP_IPV6_ROUTE) as u32;
+            if libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            ) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            fd
+        };
+        Ok(Self {
+            fd: AsyncFd::with_interest(fd, Interest::READABLE)?,
+            buffer: vec![0; RECEIVE_BUFFER_SIZE],
+        })
+    }
+
+    /// Wait for a notification about a global address or a default route
+    pub(crate) async fn next_change(&mut self) -> io::Result<()> {
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let buffer = &mut self.buffer;
+            let received = guard.try_io(|fd| {
+                // SAFETY: the buffer is valid for its whole length
+                let length = unsafe {
+                    libc::recv(
+                        fd.as_raw_fd(),
+                        buffer.as_mut_ptr() as *mut libc::c_void,
+                        buffer.len(),
+                        0,
+                    )
+                };
+                if length < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(length as usize)
+                }
+            });
+            match received {
+                Ok(Ok(length)) => {
+                    if contains_change(&self.buffer[..length]) {
+                        return Ok(());
+                    }
+                }
+                // The socket buffer overflowed, some notifications were lost
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::ENOBUFS) => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                // Spurious wake-up, the readiness has been cleared
+                Err(_would_block) => (),
+            }
+        }
+    }
+}
+
+/// Whether the netlink messages of a datagram report a change of a global address or of a default route.
+/// Link-local addresses and the routes of the local networks don't change the public addresses.
+fn contains_change(mut datagram: &[u8]) -> bool {
+    while datagram.len() >= NLMSG_HDRLEN {
+        let message_length = u32::from_ne_bytes(datagram[0..4].try_into().unwrap()) as usize;
+        let message_type = u16::from_ne_bytes(datagram[4..6].try_into().unwrap());
+        if message_length < NLMSG_HDRLEN || message_length > datagram.len() {
+            break;
+        }
+        let payload = &datagram[NLMSG_HDRLEN..message_length];
+        let is_change = match message_type {
+            // struct ifaddrmsg: family, prefix length, flags, scope, interface index
+            libc::RTM_NEWADDR | libc::RTM_DELADDR => {
+                payload.len() >= 4 && payload[3] == libc::RT_SCOPE_UNIVERSE
+            }
+            // struct rtmsg: family, destination length, ...
+            libc::RTM_NEWROUTE | libc::RTM_DELROUTE => payload.len() >= 2 && payload[1] == 0,
+            _ => false,
+        };
+        if is_change {
+            return true;
+        }
+        // Messages are aligned on 4 bytes
+        let aligned_length = (message_length + 3) & !3;
+        datagram = &datagram[aligned_length.min(datagram.len())..];
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn netlink_message(message_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend(((NLMSG_HDRLEN + payload.len()) as u32).to_ne_bytes());
+        message.extend(message_type.to_ne_bytes());
+        message.extend([0; 10]);
+        message.extend(payload);
+        message.resize((message.len() + 3) & !3, 0);
+        message
+    }
+
+    #[test]
+    fn test_contains_change() {
+        let global_address = netlink_message(libc::RTM_NEWADDR, &[10, 64, 0, 0, 2, 0, 0, 0]);
+        let link_local_address = netlink_message(
+            libc::RTM_NEWADDR,
+            &[10, 64, 0, libc::RT_SCOPE_LINK, 2, 0, 0, 0],
+        );
+        let default_route = netlink_message(libc::RTM_DELROUTE, &[2, 0, 0, 0, 254, 3, 0, 1]);
+        let local_route = netlink_message(libc::RTM_NEWROUTE, &[2, 24, 0, 0, 254, 2, 253, 1]);
+
+        assert!(contains_change(&global_address));
+        assert!(!contains_change(&link_local_address));
+        assert!(contains_change(&default_route));
+        assert!(!contains_change(&local_route));
+        assert!(contains_change(
+            &[
+                link_local_address.clone(),
+                local_route.clone(),
+                default_route
+            ]
+            .concat()
+        ));
+        assert!(!contains_change(
+            &[link_local_address, local_route].concat()
+        ));
+        assert!(!contains_change(&global_address[..NLMSG_HDRLEN + 2]));
+    }
+}
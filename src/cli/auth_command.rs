@@ -0,0 +1,193 @@
+//! `auth` subcommand: creation of a consumer key restricted to the managed records
+
+use crate::cli::env_config::create_ovh_dns_updater_with_consumer_key;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::record_zones::{resolve_zones, RecordZoneArgs};
+use clap::{Args, Subcommand};
+use log::{error, warn};
+use ovh_dns_updater::ovh::ovh_api_error::OvhApiError;
+use ovh_dns_updater::ovh_dns_updater::ovh_credential::{AccessRule, OvhCredential};
+use ovh_dns_updater::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError::OvhCredentialError;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum AuthCommand {
+    /// Request a consumer key allowed to manage only the given records, and save it into the .env file
+    Init(AuthInitArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct AuthInitArgs {
+    /// Record the consumer key will manage, can be repeated. Access is granted to the whole zone of the record
+    #[arg(long, short, required_unless_present = "config")]
+    record: Vec<String>,
+    #[command(flatten)]
+    record_zone_args: RecordZoneArgs,
+    /// File in which OVH_CONSUMER_KEY is written
+    #[arg(long, default_value = ".env")]
+    env_file: PathBuf,
+    /// Time given to validate the consumer key, in seconds
+    #[arg(long, default_value_t = 600)]
+    timeout: u64,
+}
+
+const CONSUMER_KEY_ENV_VAR: &str = "OVH_CONSUMER_KEY";
+const VALIDATION_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+
+pub(crate) async fn run_auth_command(auth_command: &AuthCommand) {
+    match auth_command {
+        AuthCommand::Init(auth_init_args) => auth_init(auth_init_args).await,
+    }
+}
+
+async fn auth_init(auth_init_args: &AuthInitArgs) {
+    let records = auth_init_args
+        .record_zone_args
+        .records(&auth_init_args.record);
+    if let Some((record, _)) = records
+        .iter()
+        .find(|(_, record_options)| record_options.discover_zone)
+    {
+        error!(
+            "The zone of record {} can't be discovered without a consumer key, give its zone instead",
+            record
+        );
+        ExitCode::UsageError.exit();
+    }
+    // No consumer key yet, requesting one only needs the application key
+    let ovh_dns_updater = create_ovh_dns_updater_with_consumer_key("");
+    let zones = resolve_zones(&records, &ovh_dns_updater).await;
+    if zones.is_empty() {
+        error!("No record uses the credentials of the environment variables");
+        ExitCode::UsageError.exit();
+    }
+    let access_rules = AccessRule::for_zones(zones.iter().map(String::as_str));
+    let credential_request = match ovh_dns_updater.request_credential(&access_rules).await {
+        Ok(credential_request) => credential_request,
+        Err(e) => {
+            error!("Cannot request consumer key: {}", e);
+            ExitCode::OvhReadError.exit();
+        }
+    };
+
+    println!("Requested access rules:");
+    for access_rule in &access_rules {
+        println!("  {} {}", access_rule.method, access_rule.path);
+    }
+    println!(
+        "Please visit the following URL to validate the consumer key:\n{}",
+        credential_request.validation_url
+    );
+
+    let new_ovh_dns_updater =
+        create_ovh_dns_updater_with_consumer_key(&credential_request.consumer_key);
+    let validation_deadline = Instant::now() + Duration::from_secs(auth_init_args.timeout);
+    println!("Waiting for validation...");
+    loop {
+        match new_ovh_dns_updater.current_credential().await {
+            Ok(credential) if credential.status == OvhCredential::STATUS_VALIDATED => break,
+            Ok(credential) if credential.status != OvhCredential::STATUS_PENDING_VALIDATION => {
+                error!("Consumer key has not been validated: {}", credential.status);
+                ExitCode::CredentialRejected.exit();
+            }
+            // OVH rejects the calls made with a consumer key until it is validated
+            Ok(_) | Err(OvhCredentialError(OvhApiError::Http { .. })) => (),
+            Err(e) => warn!("Cannot check consumer key status: {}", e),
+        }
+        if Instant::now() >= validation_deadline {
+            error!("Consumer key has not been validated in time");
+            ExitCode::CredentialRejected.exit();
+        }
+        tokio::time::sleep(VALIDATION_POLLING_INTERVAL).await;
+    }
+
+    match save_env_var(
+        &auth_init_args.env_file,
+        CONSUMER_KEY_ENV_VAR,
+        &credential_request.consumer_key,
+    ) {
+        Ok(()) => println!(
+            "Consumer key validated and saved into {}",
+            auth_init_args.env_file.display()
+        ),
+        Err(e) => {
+            // Not logged, the consumer key must only be shown to the user
+            eprintln!(
+                "Cannot write {}: {}, consumer key is {}",
+                auth_init_args.env_file.display(),
+                e,
+                credential_request.consumer_key
+            );
+            ExitCode::SetupError.exit();
+        }
+    }
+}
+
+/// Sets the variable in the dotenv file, replacing its previous value if any.
+/// The file is only readable by its owner, as it contains credentials.
+fn save_env_var(env_file: &Path, name: &str, value: &str) -> std::io::Result<()> {
+    let env_file_content = match fs::read_to_string(env_file) {
+        Ok(env_file_content) => env_file_content,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let new_env_file_content = replace_env_var(&env_file_content, name, value);
+
+    let tmp_env_file = env_file.with_extension("tmp");
+    fs::write(&tmp_env_file, new_env_file_content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_env_file, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&tmp_env_file, env_file)
+}
+
+fn replace_env_var(env_file_content: &str, name: &str, value: &str) -> String {
+    let variable_prefix = format!("{}=", name);
+    let mut replaced = false;
+    let mut lines: Vec<String> = env_file_content
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with(&variable_prefix) {
+                replaced = true;
+                format!("{}{}", variable_prefix, value)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.push(format!("{}{}", variable_prefix, value));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_env_var() {
+        assert_eq!(
+            replace_env_var(
+                "OVH_ENDPOINT=ovh-eu\nOVH_CONSUMER_KEY=old\n",
+                "OVH_CONSUMER_KEY",
+                "new"
+            ),
+            "OVH_ENDPOINT=ovh-eu\nOVH_CONSUMER_KEY=new\n"
+        );
+        assert_eq!(
+            replace_env_var("OVH_ENDPOINT=ovh-eu", "OVH_CONSUMER_KEY", "new"),
+            "OVH_ENDPOINT=ovh-eu\nOVH_CONSUMER_KEY=new\n"
+        );
+        assert_eq!(
+            replace_env_var("", "OVH_CONSUMER_KEY", "new"),
+            "OVH_CONSUMER_KEY=new\n"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! `check` subcommand: self-check of the OVH credentials
+
+use crate::cli::env_config::create_ovh_dns_updater_from_env_var;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::record_zones::{resolve_zones, RecordZoneArgs};
+use clap::Args;
+use log::{error, warn};
+use ovh_dns_updater::ovh_dns_updater::ovh_credential::OvhCredential;
+
+#[derive(Args, Debug)]
+pub(crate) struct CheckArgs {
+    /// Record that must be updatable with the credentials, can be repeated
+    #[arg(long, short)]
+    record: Vec<String>,
+    #[command(flatten)]
+    record_zone_args: RecordZoneArgs,
+}
+
+pub(crate) async fn run_check_command(check_args: &CheckArgs) {
+    let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    let records = check_args.record_zone_args.records(&check_args.record);
+    let zones = resolve_zones(&records, &ovh_dns_updater).await;
+    let credential_check = match ovh_dns_updater
+        .check_credential(zones.iter().map(String::as_str))
+        .await
+    {
+        Ok(credential_check) => credential_check,
+        Err(e) => {
+            error!("Cannot retrieve current credential: {}", e);
+            ExitCode::OvhReadError.exit();
+        }
+    };
+
+    let credential = &credential_check.credential;
+    println!("Application ID: {}", credential.application_id);
+    println!("Credential ID: {}", credential.credential_id);
+    println!("Status: {}", credential.status);
+    println!(
+        "Created: {}",
+        credential.creation.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "Expires: {}",
+        credential.expiration.as_deref().unwrap_or("never")
+    );
+    println!(
+        "Last use: {}",
+        credential.last_use.as_deref().unwrap_or("never")
+    );
+    match &credential_check.account {
+        Some(account) => println!(
+            "Account: {} ({})",
+            account.nichandle,
+            account.email.as_deref().unwrap_or("no email")
+        ),
+        None => println!("Account: unknown, GET /me is not granted"),
+    }
+    println!("Granted rules:");
+    for rule in &credential.rules {
+        println!("  {} {}", rule.method, rule.path);
+    }
+
+    if credential.status != OvhCredential::STATUS_VALIDATED {
+        warn!("Credential is not validated");
+    }
+    for missing_rule in &credential_check.missing_rules {
+        warn!(
+            "{} {} is not granted",
+            missing_rule.method, missing_rule.path
+        );
+    }
+    if !credential_check.is_usable() {
+        ExitCode::CredentialRejected.exit();
+    }
+    println!("Credentials are usable for the checked records");
+}
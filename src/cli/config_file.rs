@@ -0,0 +1,313 @@
+//! Config file listing the records updated in a single run, with per-record options
+
+use crate::cli::exit_code::ExitCode;
+use log::error;
+use ovh_dns_updater::ovh_dns_updater::ip_family::IpFamily;
+use ovh_dns_updater::ovh_dns_updater::record_options::{
+    DuplicateRecordPolicy, LostAddressPolicy, RecordOptions, TtlPolicy,
+};
+use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
+use ovh_dns_updater::public_ip_retriever::ip_source::IpSource;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Content of a config file:
+/// ```toml
+/// concurrency = 4
+///
+/// [profile.customer]
+/// endpoint = "ovh-eu"
+/// application_key = "..."
+/// application_secret = "..."
+/// consumer_key = "..."
+///
+/// [[record]]
+/// name = "home.example.com"
+/// ipv6 = true
+/// ttl = 300
+///
+/// [[record]]
+/// name = "vpn.customer.example.org"
+/// profile = "customer"
+/// ip_source = "ipify"
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    /// Maximum number of records updated at once
+    #[serde(default = "default_concurrency")]
+    pub(crate) concurrency: usize,
+    /// OVH credentials by profile name, records without profile use the environment variables
+    #[serde(rename = "profile", default)]
+    pub(crate) profiles: BTreeMap<String, CredentialsProfile>,
+    #[serde(rename = "record", default)]
+    pub(crate) records: Vec<RecordConfig>,
+}
+
+/// OVH API credentials
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CredentialsProfile {
+    /// Either a known endpoint name, e.g. "ovh-eu", or the base URL of the API
+    pub(crate) endpoint: String,
+    pub(crate) application_key: String,
+    pub(crate) application_secret: String,
+    pub(crate) consumer_key: String,
+}
+
+/// A record to update with the public IP addresses, and its options
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecordConfig {
+    /// The full domain name, e.g. "home.example.com"
+    pub(crate) name: String,
+    /// Update the A record
+    #[serde(default = "default_true")]
+    pub(crate) ipv4: bool,
+    /// Update the AAAA record
+    #[serde(default)]
+    pub(crate) ipv6: bool,
+    /// "preserve", "default" or a number of seconds
+    #[serde(default, deserialize_with = "deserialize_ttl_policy")]
+    pub(crate) ttl: TtlPolicy,
+    /// DNS zone of the record, when it isn't the registered domain
+    pub(crate) zone: Option<String>,
+    /// Find the zone of the record among the DNS zones of the OVH account
+    #[serde(default)]
+    pub(crate) discover_zone: bool,
+    /// Name of the credentials profile, None to use the environment variables
+    pub(crate) profile: Option<String>,
+    /// Service asked for the public IP address
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) ip_source: IpSource,
+    /// Create the record if it doesn't exist yet
+    #[serde(default)]
+    pub(crate) create: bool,
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) duplicates: DuplicateRecordPolicy,
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) on_ip4_lost: LostAddressPolicy,
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) on_ip6_lost: LostAddressPolicy,
+}
+
+fn default_concurrency() -> usize {
+    4
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Deserialize a value from its string representation, e.g. "update-all"
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// The TTL is either a number of seconds or "preserve" / "default"
+fn deserialize_ttl_policy<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<TtlPolicy, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TtlValue {
+        Seconds(u32),
+        Policy(String),
+    }
+    match TtlValue::deserialize(deserializer)? {
+        TtlValue::Seconds(seconds) => Ok(TtlPolicy::Seconds(seconds)),
+        TtlValue::Policy(policy) => policy.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+impl ConfigFile {
+    /// Parse and check a config file
+    /// # Arguments
+    /// * `toml_content` - The content of the TOML file
+    /// # Returns
+    /// * `Err(String)` - If the file can't be parsed, a profile has an invalid endpoint,
+    ///   or a record refers to an unknown profile or has inconsistent options
+    pub(crate) fn from_toml(toml_content: &str) -> Result<Self, String> {
+        let config_file: Self = toml::from_str(toml_content).map_err(|e| e.to_string())?;
+        if config_file.concurrency == 0 {
+            return Err("concurrency must be at least 1".to_string());
+        }
+        for (profile_name, profile) in &config_file.profiles {
+            profile
+                .check()
+                .map_err(|e| format!("profile \"{}\": {}", profile_name, e))?;
+        }
+        for record_config in &config_file.records {
+            record_config
+                .check(&config_file.profiles)
+                .map_err(|e| format!("record \"{}\": {}", record_config.name, e))?;
+        }
+        Ok(config_file)
+    }
+
+    /// Read and check a config file
+    pub(crate) fn from_file(config_path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(config_path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| Self::from_toml(&content))
+    }
+}
+
+/// Read the config file, exits on failure
+pub(crate) fn read_config_file(config_path: &Path) -> ConfigFile {
+    match ConfigFile::from_file(config_path) {
+        Ok(config_file) => config_file,
+        Err(e) => {
+            error!("Cannot read config file {}: {}", config_path.display(), e);
+            ExitCode::InvalidConfigFile.exit();
+        }
+    }
+}
+
+impl CredentialsProfile {
+    /// Checks the endpoint, so that an invalid profile is found when the file is read rather than when it is used
+    fn check(&self) -> Result<(), String> {
+        OvhDnsUpdater::check_endpoint(&self.endpoint).map_err(|e| e.to_string())
+    }
+}
+
+impl RecordConfig {
+    fn check(&self, profiles: &BTreeMap<String, CredentialsProfile>) -> Result<(), String> {
+        if !self.ipv4 && !self.ipv6 {
+            return Err("neither ipv4 nor ipv6 is enabled".to_string());
+        }
+        if self.zone.is_some() && self.discover_zone {
+            return Err("zone and discover_zone can't be used together".to_string());
+        }
+        if let Some(profile) = self
+            .profile
+            .as_ref()
+            .filter(|profile| !profiles.contains_key(*profile))
+        {
+            return Err(format!("unknown profile \"{}\"", profile));
+        }
+        for (lost_address_policy, ip_family) in [
+            (&self.on_ip4_lost, IpFamily::V4),
+            (&self.on_ip6_lost, IpFamily::V6),
+        ] {
+            if let LostAddressPolicy::Fallback(fallback_ip) = lost_address_policy {
+                if !ip_family.contains(fallback_ip) {
+                    return Err(format!(
+                        "fallback address {} isn't an {} address",
+                        fallback_ip, ip_family
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The address families to update, IPv4 first
+    pub(crate) fn ip_families(&self) -> Vec<IpFamily> {
+        [(self.ipv4, IpFamily::V4), (self.ipv6, IpFamily::V6)]
+            .into_iter()
+            .filter_map(|(enabled, ip_family)| enabled.then_some(ip_family))
+            .collect()
+    }
+
+    /// The options used to update the record of the given family
+    pub(crate) fn record_options(&self, ip_family: IpFamily) -> RecordOptions {
+        RecordOptions {
+            create_if_missing: self.create,
+            ttl: self.ttl,
+            duplicates: self.duplicates,
+            zone: self.zone.clone(),
+            discover_zone: self.discover_zone,
+            lost_address: match ip_family {
+                IpFamily::V4 => self.on_ip4_lost,
+                IpFamily::V6 => self.on_ip6_lost,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_FILE: &str = r#"
+        concurrency = 2
+
+        [profile.customer]
+        endpoint = "ovh-eu"
+        application_key = "ak"
+        application_secret = "as"
+        consumer_key = "ck"
+
+        [[record]]
+        name = "home.example.com"
+        ipv6 = true
+        ttl = 300
+        on_ip6_lost = "delete"
+
+        [[record]]
+        name = "vpn.customer.example.org"
+        ipv4 = false
+        ipv6 = true
+        profile = "customer"
+        ip_source = "ipify"
+        ttl = "default"
+        duplicates = "update-all"
+    "#;
+
+    #[test]
+    fn test_config_file_from_toml() {
+        let config_file = ConfigFile::from_toml(CONFIG_FILE).unwrap();
+        assert_eq!(config_file.concurrency, 2);
+        assert_eq!(config_file.records.len(), 2);
+
+        let home_record = &config_file.records[0];
+        assert_eq!(home_record.ip_families(), vec![IpFamily::V4, IpFamily::V6]);
+        assert_eq!(home_record.ttl, TtlPolicy::Seconds(300));
+        assert_eq!(home_record.profile, None);
+        assert_eq!(
+            home_record.record_options(IpFamily::V6).lost_address,
+            LostAddressPolicy::Delete
+        );
+        assert_eq!(
+            home_record.record_options(IpFamily::V4).lost_address,
+            LostAddressPolicy::Keep
+        );
+
+        let vpn_record = &config_file.records[1];
+        assert_eq!(vpn_record.ip_families(), vec![IpFamily::V6]);
+        assert_eq!(vpn_record.ttl, TtlPolicy::ZoneDefault);
+        assert_eq!(vpn_record.ip_source, IpSource::Ipify);
+        assert_eq!(vpn_record.duplicates, DuplicateRecordPolicy::UpdateAll);
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        for invalid_config in [
+            "[[record]]\nname = \"home.example.com\"\nprofile = \"unknown\"",
+            "[[record]]\nname = \"home.example.com\"\nipv4 = false",
+            "[[record]]\nname = \"home.example.com\"\nzone = \"example.com\"\ndiscover_zone = true",
+            "[[record]]\nname = \"home.example.com\"\non_ip4_lost = \"::1\"",
+            "[[record]]\nname = \"home.example.com\"\nip_source = \"unknown\"",
+            "concurrency = 0",
+            "[profile.customer]\nendpoint = \"ovh-mars\"\napplication_key = \"ak\"\n\
+             application_secret = \"as\"\nconsumer_key = \"ck\"",
+        ] {
+            assert!(
+                ConfigFile::from_toml(invalid_config).is_err(),
+                "{}",
+                invalid_config
+            );
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! `daemon` subcommand: keep the records of a config file up to date, instead of running from cron
+
+use crate::cli::config_file::{read_config_file, ConfigFile};
+use crate::cli::exit_code::ExitCode;
+use crate::cli::record_sync::RecordSyncer;
+use crate::cli::state_file::StateFileArgs;
+use clap::Args;
+use futures::future;
+use log::{error, info, warn};
+use ovh_dns_updater::address_watcher::AddressWatcher;
+use ovh_dns_updater::ovh::retry_policy::RetryPolicy;
+use ovh_dns_updater::ovh_dns_updater::zone_refresh::ZoneRefreshPolicy;
+use rand::Rng;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The time difference with the OVH API server may drift while the daemon runs
+const TIME_DELTA_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Args, Debug)]
+pub(crate) struct DaemonArgs {
+    /// TOML file listing the records to update, reloaded on SIGHUP
+    #[arg(long)]
+    config: PathBuf,
+    /// Seconds between two synchronizations
+    #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+    /// Maximum random number of seconds added to each interval, so that many hosts don't call OVH at the same time
+    #[arg(long, default_value_t = 30)]
+    jitter: u64,
+    /// Don't synchronize at once when the local addresses or default routes change (only watched on Linux)
+    #[arg(long)]
+    no_address_watch: bool,
+    /// Seconds without local address change before synchronizing, a change often comes with several notifications
+    #[arg(long, default_value_t = 2)]
+    debounce: u64,
+    /// Don't refresh the DNS zone after a change, OVH will publish it later
+    #[arg(long)]
+    no_zone_refresh: bool,
+    /// Number of retries of OVH API requests after a transient failure
+    #[arg(long, default_value_t = RetryPolicy::default().max_retries)]
+    retries: u32,
+    #[command(flatten)]
+    state_file_args: StateFileArgs,
+}
+
+/// Synchronize the records, then wait for the interval or a local address change, until SIGTERM or SIGINT is received.
+/// A synchronization in progress is completed before stopping. SIGHUP reloads the config file and synchronizes at once.
+pub(crate) async fn run_daemon_command(daemon_args: &DaemonArgs) {
+    let mut daemon_signals = DaemonSignals::new();
+    let mut address_watcher = create_address_watcher(daemon_args);
+    let mut record_syncer =
+        create_record_syncer(daemon_args, read_config_file(&daemon_args.config))
+            .unwrap_or_else(|exit_code| exit_code.exit());
+    loop {
+        if let Err(exit_code) = record_syncer.sync().await {
+            error!("Synchronization failed with exit code {}", exit_code.code());
+        }
+        let delay = Duration::from_secs(daemon_args.interval)
+            + rand::thread_rng()
+                .gen_range(Duration::ZERO..=Duration::from_secs(daemon_args.jitter));
+        info!("Next synchronization in {} seconds", delay.as_secs());
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        // An invalid config file doesn't interrupt the wait
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                address_change = address_changed(&mut address_watcher) => match address_change {
+                    Ok(()) => {
+                        info!("Local addresses changed");
+                        break;
+                    }
+                    Err(e) => {
+                        warn!("Cannot watch local address changes anymore: {}", e);
+                        address_watcher = None;
+                    }
+                },
+                daemon_signal = daemon_signals.recv() => match daemon_signal {
+                    DaemonSignal::Stop => {
+                        info!("Stopping");
+                        return;
+                    }
+                    DaemonSignal::Reload => match reload_record_syncer(daemon_args) {
+                        Some(reloaded_record_syncer) => {
+                            info!("Config file {} reloaded", daemon_args.config.display());
+                            record_syncer = reloaded_record_syncer;
+                            break;
+                        }
+                        None => error!(
+                            "Config file {} not reloaded, keeping the previous one",
+                            daemon_args.config.display()
+                        ),
+                    },
+                },
+            }
+        }
+    }
+}
+
+/// Read the config file again and create its syncer, None if either fails, the error is logged
+fn reload_record_syncer(daemon_args: &DaemonArgs) -> Option<RecordSyncer> {
+    let config_file = match ConfigFile::from_file(&daemon_args.config) {
+        Ok(config_file) => config_file,
+        Err(e) => {
+            error!(
+                "Cannot read config file {}: {}",
+                daemon_args.config.display(),
+                e
+            );
+            return None;
+        }
+    };
+    create_record_syncer(daemon_args, config_file).ok()
+}
+
+/// Returns `Err(exit_code)` if a DNS updater can't be created, the error is logged
+fn create_record_syncer(
+    daemon_args: &DaemonArgs,
+    config_file: ConfigFile,
+) -> Result<RecordSyncer, ExitCode> {
+    let retry_policy = RetryPolicy {
+        max_retries: daemon_args.retries,
+        ..RetryPolicy::default()
+    };
+    let zone_refresh_policy = if daemon_args.no_zone_refresh {
+        ZoneRefreshPolicy::Skip
+    } else {
+        ZoneRefreshPolicy::Deferred
+    };
+    let mut record_syncer = RecordSyncer::new(config_file, &retry_policy, zone_refresh_policy)?;
+    record_syncer.set_time_delta_refresh_interval(Some(TIME_DELTA_REFRESH_INTERVAL));
+    // Reloaded from the disk with the config file, it is saved after each synchronization
+    if let Some(state_file) = daemon_args.state_file_args.load_state_file() {
+        record_syncer.set_state_file(state_file);
+    }
+    Ok(record_syncer)
+}
+
+/// Returns None if the local addresses aren't watched
+fn create_address_watcher(daemon_args: &DaemonArgs) -> Option<AddressWatcher> {
+    if daemon_args.no_address_watch {
+        return None;
+    }
+    match AddressWatcher::new(Duration::from_secs(daemon_args.debounce)) {
+        Ok(address_watcher) => Some(address_watcher),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => None,
+        Err(e) => {
+            warn!("Cannot watch local address changes: {}", e);
+            None
+        }
+    }
+}
+
+/// Never completes if the local addresses aren't watched
+async fn address_changed(address_watcher: &mut Option<AddressWatcher>) -> io::Result<()> {
+    match address_watcher {
+        Some(address_watcher) => address_watcher.changed().await,
+        None => future::pending().await,
+    }
+}
+
+enum DaemonSignal {
+    Stop,
+    Reload,
+}
+
+/// Signals listened to by the daemon, registered once so that a signal received during a synchronization isn't lost
+#[cfg(unix)]
+struct DaemonSignals {
+    terminate: tokio::signal::unix::Signal,
+    interrupt: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl DaemonSignals {
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+        let register = |signal_kind| match signal(signal_kind) {
+            Ok(signal) => signal,
+            Err(e) => {
+                error!("Cannot listen to signals: {}", e);
+                ExitCode::SetupError.exit();
+            }
+        };
+        Self {
+            terminate: register(SignalKind::terminate()),
+            interrupt: register(SignalKind::interrupt()),
+            hangup: register(SignalKind::hangup()),
+        }
+    }
+
+    async fn recv(&mut self) -> DaemonSignal {
+        tokio::select! {
+            _ = self.terminate.recv() => DaemonSignal::Stop,
+            _ = self.interrupt.recv() => DaemonSignal::Stop,
+            _ = self.hangup.recv() => DaemonSignal::Reload,
+        }
+    }
+}
+
+/// Only Ctrl-C is available, the config file can't be reloaded
+#[cfg(windows)]
+struct DaemonSignals {
+    ctrl_c: tokio::signal::windows::CtrlC,
+}
+
+#[cfg(windows)]
+impl DaemonSignals {
+    fn new() -> Self {
+        match tokio::signal::windows::ctrl_c() {
+            Ok(ctrl_c) => Self { ctrl_c },
+            Err(e) => {
+                error!("Cannot listen to Ctrl-C: {}", e);
+                ExitCode::SetupError.exit();
+            }
+        }
+    }
+
+    async fn recv(&mut self) -> DaemonSignal {
+        self.ctrl_c.recv().await;
+        DaemonSignal::Stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct DaemonCli {
+        #[command(flatten)]
+        daemon_args: DaemonArgs,
+    }
+
+    #[test]
+    fn test_reload_record_syncer() {
+        let path = std::env::temp_dir().join(format!(
+            "ovh_dns_updater_daemon_{}.toml",
+            std::process::id()
+        ));
+        let daemon_args =
+            DaemonCli::parse_from(["ovh_dns_updater", "--config", path.to_str().unwrap()])
+                .daemon_args;
+        let config_file = |endpoint| {
+            format!(
+                "[profile.customer]\nendpoint = \"{}\"\napplication_key = \"ak\"\n\
+                 application_secret = \"as\"\nconsumer_key = \"ck\"\n\n\
+                 [[record]]\nname = \"home.example.com\"\nprofile = \"customer\"\n",
+                endpoint
+            )
+        };
+
+        std::fs::write(&path, config_file("ovh-eu")).unwrap();
+        assert!(reload_record_syncer(&daemon_args).is_some());
+        // The daemon keeps running with its previous config file
+        std::fs::write(&path, config_file("ovh-mars")).unwrap();
+        assert!(reload_record_syncer(&daemon_args).is_none());
+        std::fs::remove_file(&path).unwrap();
+        assert!(reload_record_syncer(&daemon_args).is_none());
+    }
+}
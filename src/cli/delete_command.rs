@@ -0,0 +1,122 @@
+//! `delete` subcommand: remove the records of a domain
+
+use crate::cli::dry_run::{print_dry_run_notice, print_planned_requests};
+use crate::cli::env_config::create_ovh_dns_updater_from_env_var;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::prompt::confirm;
+use clap::Args;
+use log::error;
+use ovh_dns_updater::ovh_dns_updater::dns_record::DnsRecordType;
+use ovh_dns_updater::ovh_dns_updater::record_options::RecordOptions;
+use ovh_dns_updater::ovh_dns_updater::zone_refresh::ZoneRefreshPolicy;
+
+#[derive(Args, Debug)]
+pub(crate) struct DeleteArgs {
+    /// The domain whose records are deleted, e.g. "subdomain.mydomain.com"
+    #[arg(long, short)]
+    record: String,
+    /// Type of the records to delete, can be repeated
+    #[arg(long = "type", short = 't', default_values_t = [DnsRecordType::A, DnsRecordType::Aaaa])]
+    record_types: Vec<DnsRecordType>,
+    /// DNS zone of the record, when it isn't the registered domain, e.g. a delegated sub-zone
+    #[arg(long)]
+    zone: Option<String>,
+    /// Find the zone of the record among the DNS zones of the OVH account
+    #[arg(long, conflicts_with = "zone")]
+    discover_zone: bool,
+    /// Delete the records without asking for confirmation
+    #[arg(long, short)]
+    yes: bool,
+    /// Look up the records, but only print the requests that would delete them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub(crate) async fn run_delete_command(delete_args: &DeleteArgs) {
+    let mut ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    // The zone is refreshed once, after all the deletions
+    ovh_dns_updater.set_zone_refresh_policy(ZoneRefreshPolicy::Deferred);
+    if delete_args.dry_run {
+        print_dry_run_notice();
+        ovh_dns_updater.set_dry_run(true);
+    }
+    let record_options = RecordOptions {
+        zone: delete_args.zone.clone(),
+        discover_zone: delete_args.discover_zone,
+        ..RecordOptions::default()
+    };
+
+    let mut dns_records_to_delete = Vec::new();
+    for record_type in &delete_args.record_types {
+        let dns_records = match ovh_dns_updater
+            .find_records(&delete_args.record, *record_type, &record_options)
+            .await
+        {
+            Ok(dns_records) => dns_records,
+            Err(e) => {
+                error!(
+                    "Cannot retrieve {} records of {}: {}",
+                    record_type, delete_args.record, e
+                );
+                ExitCode::OvhReadError.exit();
+            }
+        };
+        for dns_record in &dns_records {
+            println!(
+                "{} {} {} (record {}, TTL {})",
+                dns_record.full_domain(),
+                dns_record.record_type,
+                dns_record.target,
+                dns_record.id.unwrap_or_default(),
+                dns_record.ttl
+            );
+        }
+        dns_records_to_delete.extend(dns_records);
+    }
+    if dns_records_to_delete.is_empty() {
+        println!("No record to delete");
+        return;
+    }
+    // Nothing is deleted in dry run, there is nothing to confirm
+    if !delete_args.yes && !delete_args.dry_run && !confirm("Delete these records?") {
+        println!("Nothing deleted");
+        return;
+    }
+
+    // Only the records shown and confirmed, even if others were added since
+    let mut exit_code = ExitCode::Success;
+    for dns_record in &dns_records_to_delete {
+        let Some(record_id) = dns_record.id else {
+            continue;
+        };
+        // The refresh is deferred, its errors are reported below
+        match ovh_dns_updater
+            .delete_record_by_id(&dns_record.zone, record_id)
+            .await
+        {
+            // In dry run, the planned requests are the only report of the deletions
+            Ok(_) if delete_args.dry_run => (),
+            Ok(_) => println!("Record {} deleted", record_id),
+            Err(e) => {
+                error!("Cannot delete record {}: {}", record_id, e);
+                exit_code = ExitCode::OvhWriteError;
+            }
+        }
+    }
+    for (zone, refresh_result) in ovh_dns_updater.refresh_pending_zones().await {
+        match refresh_result {
+            Ok(()) if delete_args.dry_run => (),
+            Ok(()) => println!("Zone {} refreshed", zone),
+            Err(e) => {
+                error!("Cannot refresh zone {}: {}", zone, e);
+                if exit_code == ExitCode::Success {
+                    exit_code = ExitCode::ZoneRefreshError;
+                }
+            }
+        }
+    }
+    print_planned_requests(&ovh_dns_updater.take_planned_requests());
+    if exit_code != ExitCode::Success {
+        exit_code.exit();
+    }
+}
@@ -0,0 +1,15 @@
+//! Dry run of the commands modifying records: the OVH requests are printed instead of being sent
+
+use ovh_dns_updater::ovh::planned_request::PlannedRequest;
+
+/// Printed before the changes of a dry run
+pub(crate) fn print_dry_run_notice() {
+    println!("Dry run: the changes below are not sent to OVH");
+}
+
+/// Print the requests that the dry run didn't send, on stdout as they are the result of the command, whatever the log level
+pub(crate) fn print_planned_requests(planned_requests: &[PlannedRequest]) {
+    for planned_request in planned_requests {
+        println!("Not sent: {}", planned_request);
+    }
+}
@@ -0,0 +1,60 @@
+//! Reads the OVH credentials from the environment (or the .env file)
+
+use crate::cli::exit_code::ExitCode;
+use log::error;
+use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
+use std::env;
+
+/// Returns the value of the given env variable, logs an error if it is not set
+fn required_env_var(name: &str) -> Result<String, ExitCode> {
+    env::var(name).map_err(|_| {
+        error!("{} env variable must be set", name);
+        ExitCode::SetupError
+    })
+}
+
+/// Creates the DNS updater from OVH_ENDPOINT, OVH_APPLICATION_KEY, OVH_APPLICATION_SECRET
+/// and the given consumer key
+/// # Returns
+/// * `Err(exit_code)` - If a variable is missing or invalid, the error is logged
+fn try_create_ovh_dns_updater_with_consumer_key(
+    ovh_consumer_key: &str,
+) -> Result<OvhDnsUpdater, ExitCode> {
+    dotenv::dotenv().ok();
+    let endpoint = required_env_var("OVH_ENDPOINT")?;
+    let ovh_application_key = required_env_var("OVH_APPLICATION_KEY")?;
+    let ovh_application_secret = required_env_var("OVH_APPLICATION_SECRET")?;
+    OvhDnsUpdater::new(
+        &endpoint,
+        &ovh_application_key,
+        &ovh_application_secret,
+        ovh_consumer_key,
+    )
+    .map_err(|e| {
+        error!("Error when creating OVH DNS updater: {}", e);
+        ExitCode::UsageError
+    })
+}
+
+/// Creates the DNS updater from OVH_ENDPOINT, OVH_APPLICATION_KEY, OVH_APPLICATION_SECRET
+/// and the given consumer key, exits on failure
+pub(crate) fn create_ovh_dns_updater_with_consumer_key(ovh_consumer_key: &str) -> OvhDnsUpdater {
+    try_create_ovh_dns_updater_with_consumer_key(ovh_consumer_key)
+        .unwrap_or_else(|exit_code| exit_code.exit())
+}
+
+/// Creates the DNS updater from OVH_ENDPOINT, OVH_APPLICATION_KEY, OVH_APPLICATION_SECRET
+/// and OVH_CONSUMER_KEY
+/// # Returns
+/// * `Err(exit_code)` - If a variable is missing or invalid, the error is logged
+pub(crate) fn try_create_ovh_dns_updater_from_env_var() -> Result<OvhDnsUpdater, ExitCode> {
+    dotenv::dotenv().ok();
+    let ovh_consumer_key = required_env_var("OVH_CONSUMER_KEY")?;
+    try_create_ovh_dns_updater_with_consumer_key(&ovh_consumer_key)
+}
+
+/// Creates the DNS updater from OVH_ENDPOINT, OVH_APPLICATION_KEY, OVH_APPLICATION_SECRET
+/// and OVH_CONSUMER_KEY, exits on failure
+pub(crate) fn create_ovh_dns_updater_from_env_var() -> OvhDnsUpdater {
+    try_create_ovh_dns_updater_from_env_var().unwrap_or_else(|exit_code| exit_code.exit())
+}
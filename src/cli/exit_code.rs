@@ -0,0 +1,60 @@
+//! Exit codes of the program, scripts and monitoring rely on them so their values never change
+
+use serde::Serialize;
+
+/// Why the program failed. Configuration errors (1, 2, 8) happen before any record is synchronized,
+/// the codes of a single record failure (3 to 7) are also used when all the records failed,
+/// and 9 is used when only some of the records of a config file failed.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExitCode {
+    Success = 0,
+    /// A required environment variable or argument is missing or invalid, or a file can't be written
+    SetupError = 1,
+    /// The command line can't be parsed, the credentials can't be used, e.g. an unknown endpoint,
+    /// or a confirmation can't be asked
+    UsageError = 2,
+    /// The public IP address can't be retrieved and the record is kept
+    PublicIpUnavailable = 3,
+    /// A record, a zone or the credential can't be read from OVH
+    OvhReadError = 4,
+    /// A record can't be created, updated or deleted
+    OvhWriteError = 5,
+    /// A zone can't be refreshed, the changes aren't published yet
+    ZoneRefreshError = 6,
+    /// The consumer key isn't validated or doesn't have the required rights
+    CredentialRejected = 7,
+    /// The config file or the zone state file can't be read or is invalid
+    InvalidConfigFile = 8,
+    /// Some records of the config file were synchronized, the others failed
+    PartialFailure = 9,
+}
+
+impl ExitCode {
+    pub(crate) fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Exit the program with this code
+    pub(crate) fn exit(self) -> ! {
+        std::process::exit(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        assert_eq!(ExitCode::Success.code(), 0);
+        assert_eq!(ExitCode::UsageError.code(), 2);
+        assert_eq!(ExitCode::PublicIpUnavailable.code(), 3);
+        assert_eq!(ExitCode::ZoneRefreshError.code(), 6);
+        assert_eq!(ExitCode::PartialFailure.code(), 9);
+        assert_eq!(
+            serde_json::to_string(&ExitCode::OvhWriteError).unwrap(),
+            "\"ovh_write_error\""
+        );
+    }
+}
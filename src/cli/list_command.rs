@@ -0,0 +1,109 @@
+//! `zones` and `records` subcommands: show what the OVH credentials can reach
+
+use crate::cli::env_config::create_ovh_dns_updater_from_env_var;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::output_format::OutputFormat;
+use clap::Args;
+use log::error;
+use ovh_dns_updater::ovh_dns_updater::dns_record::{DnsRecord, DnsRecordType};
+
+#[derive(Args, Debug)]
+pub(crate) struct ZonesArgs {
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::default())]
+    output: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RecordsArgs {
+    /// The DNS zone, e.g. "example.com"
+    zone: String,
+    /// Only show the records of this type, e.g. "TXT"
+    #[arg(long = "type", short = 't')]
+    record_type: Option<DnsRecordType>,
+    /// Only show the records of this subdomain, "@" for the zone apex
+    #[arg(long, short)]
+    sub_domain: Option<String>,
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::default())]
+    output: OutputFormat,
+}
+
+pub(crate) async fn run_zones_command(zones_args: &ZonesArgs) {
+    let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    let zones = match ovh_dns_updater.list_zones().await {
+        Ok(zones) => zones,
+        Err(e) => {
+            error!("Cannot retrieve DNS zones: {}", e);
+            ExitCode::OvhReadError.exit();
+        }
+    };
+    match zones_args.output {
+        OutputFormat::Table => zones.iter().for_each(|zone| println!("{}", zone)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&zones).unwrap()),
+    }
+}
+
+pub(crate) async fn run_records_command(records_args: &RecordsArgs) {
+    let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    let sub_domain =
+        records_args
+            .sub_domain
+            .as_deref()
+            .map(|sub_domain| if sub_domain == "@" { "" } else { sub_domain });
+    let dns_records = match ovh_dns_updater
+        .get_records(&records_args.zone, records_args.record_type, sub_domain)
+        .await
+    {
+        Ok(dns_records) => dns_records,
+        Err(e) => {
+            error!(
+                "Cannot retrieve records of zone {}: {}",
+                records_args.zone, e
+            );
+            ExitCode::OvhReadError.exit();
+        }
+    };
+    match records_args.output {
+        OutputFormat::Table => print_records_table(&dns_records),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&dns_records).unwrap()),
+    }
+}
+
+fn print_records_table(dns_records: &[DnsRecord]) {
+    let rows: Vec<[String; 5]> = dns_records
+        .iter()
+        .map(|dns_record| {
+            [
+                dns_record.id.map_or_else(String::new, |id| id.to_string()),
+                if dns_record.sub_domain.is_empty() {
+                    "@".to_string()
+                } else {
+                    dns_record.sub_domain.clone()
+                },
+                dns_record.record_type.to_string(),
+                dns_record.ttl.to_string(),
+                dns_record.target.clone(),
+            ]
+        })
+        .collect();
+    let header = ["ID", "SUBDOMAIN", "TYPE", "TTL", "TARGET"].map(String::from);
+    let mut widths = header.clone().map(|column| column.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        println!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        );
+    }
+}
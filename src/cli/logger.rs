@@ -0,0 +1,262 @@
+//! Writes the logs of the commands and of the library to stderr, journald or syslog
+
+use crate::cli::exit_code::ExitCode;
+use clap::{ArgAction, Args, ValueEnum};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::borrow::Cow;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// Name of the program in journald and syslog, also the prefix of the log targets of its modules
+const IDENTIFIER: &str = env!("CARGO_PKG_NAME");
+#[cfg(unix)]
+const JOURNALD_SOCKET_PATH: &str = "/run/systemd/journal/socket";
+#[cfg(unix)]
+const SYSLOG_SOCKET_PATH: &str = "/dev/log";
+/// Syslog facility of the messages, "daemon"
+#[cfg(unix)]
+const SYSLOG_FACILITY: u8 = 3;
+/// Prefix of the OVH request signatures, followed by a SHA-1 digest in hexadecimal
+const SIGNATURE_PREFIX: &str = "$1$";
+const SIGNATURE_DIGEST_LEN: usize = 40;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum LogTarget {
+    #[default]
+    Stderr,
+    Journald,
+    Syslog,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct LogArgs {
+    /// Only log errors, and don't print the progress
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Log the OVH requests and the answers of the public address services, with -vv also their content
+    #[arg(long, short, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Where logs are written: "stderr", "journald" or "syslog"
+    #[arg(long, global = true, value_enum, default_value_t = LogTarget::default(), hide_possible_values = true)]
+    log_target: LogTarget,
+}
+
+impl LogArgs {
+    fn level_filter(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::Error,
+            (false, 0) => LevelFilter::Info,
+            (false, 1) => LevelFilter::Debug,
+            (false, _) => LevelFilter::Trace,
+        }
+    }
+
+    /// Install the logger, exits if journald or syslog can't be reached
+    pub(crate) fn init_logger(&self) {
+        let output = match LogOutput::open(self.log_target) {
+            Ok(output) => output,
+            Err(e) => {
+                let log_target = self.log_target.to_possible_value().unwrap();
+                eprintln!("Cannot log to {}: {}", log_target.get_name(), e);
+                ExitCode::SetupError.exit();
+            }
+        };
+        let level_filter = self.level_filter();
+        log::set_boxed_logger(Box::new(Logger {
+            output,
+            level_filter,
+        }))
+        .expect("the logger is only installed once");
+        log::set_max_level(level_filter);
+    }
+}
+
+enum LogOutput {
+    Stderr,
+    #[cfg(unix)]
+    Journald(UnixDatagram),
+    #[cfg(unix)]
+    Syslog(UnixDatagram),
+}
+
+impl LogOutput {
+    fn open(log_target: LogTarget) -> io::Result<Self> {
+        match log_target {
+            LogTarget::Stderr => Ok(LogOutput::Stderr),
+            #[cfg(unix)]
+            LogTarget::Journald => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(JOURNALD_SOCKET_PATH)?;
+                Ok(LogOutput::Journald(socket))
+            }
+            #[cfg(unix)]
+            LogTarget::Syslog => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(SYSLOG_SOCKET_PATH)?;
+                Ok(LogOutput::Syslog(socket))
+            }
+            #[cfg(not(unix))]
+            LogTarget::Journald | LogTarget::Syslog => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only available on Unix",
+            )),
+        }
+    }
+}
+
+struct Logger {
+    output: LogOutput,
+    level_filter: LevelFilter,
+}
+
+impl Log for Logger {
+    /// Only the logs of this program, those of its dependencies, e.g. reqwest, are too detailed
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter && metadata.target().starts_with(IDENTIFIER)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let message = redact_signatures(&message);
+        let sent = match &self.output {
+            LogOutput::Stderr => false,
+            #[cfg(unix)]
+            LogOutput::Journald(socket) => socket
+                .send(&journald_datagram(
+                    record.level(),
+                    record.target(),
+                    &message,
+                ))
+                .is_ok(),
+            #[cfg(unix)]
+            LogOutput::Syslog(socket) => socket
+                .send(syslog_datagram(record.level(), &message).as_bytes())
+                .is_ok(),
+        };
+        // Logs aren't lost when journald or syslog can't be reached, e.g. while it restarts
+        if sent {
+            return;
+        }
+        match record.level() {
+            Level::Error | Level::Warn | Level::Info => {
+                eprintln!("{}: {}", level_name(record.level()), message)
+            }
+            Level::Debug | Level::Trace => eprintln!(
+                "{} {}: {}",
+                level_name(record.level()),
+                record.target(),
+                message
+            ),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warn => "warning",
+        Level::Info => "info",
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+    }
+}
+
+/// Syslog severity, also the journald priority
+#[cfg(unix)]
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Message of the journald native protocol
+#[cfg(unix)]
+fn journald_datagram(level: Level, target: &str, message: &str) -> Vec<u8> {
+    let mut datagram = format!(
+        "PRIORITY={}\nSYSLOG_IDENTIFIER={}\nSYSLOG_PID={}\nCODE_MODULE={}\n",
+        severity(level),
+        IDENTIFIER,
+        std::process::id(),
+        target
+    )
+    .into_bytes();
+    // The binary form of the field allows new lines in the message, e.g. in a response body
+    datagram.extend_from_slice(b"MESSAGE\n");
+    datagram.extend_from_slice(&(message.len() as u64).to_le_bytes());
+    datagram.extend_from_slice(message.as_bytes());
+    datagram.push(b'\n');
+    datagram
+}
+
+/// Message of the local syslog protocol, the daemon adds the time and the host name
+#[cfg(unix)]
+fn syslog_datagram(level: Level, message: &str) -> String {
+    format!(
+        "<{}>{}[{}]: {}",
+        SYSLOG_FACILITY * 8 + severity(level),
+        IDENTIFIER,
+        std::process::id(),
+        message
+    )
+}
+
+/// Hides the OVH request signatures, the library never logs them but they must not leak
+fn redact_signatures(message: &str) -> Cow<'_, str> {
+    if !message.contains(SIGNATURE_PREFIX) {
+        return Cow::Borrowed(message);
+    }
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(prefix_index) = rest.find(SIGNATURE_PREFIX) {
+        let digest_index = prefix_index + SIGNATURE_PREFIX.len();
+        let digest_len = rest[digest_index..]
+            .bytes()
+            .take_while(u8::is_ascii_hexdigit)
+            .count();
+        redacted.push_str(&rest[..digest_index]);
+        if digest_len == SIGNATURE_DIGEST_LEN {
+            redacted.push_str("***");
+            rest = &rest[digest_index + digest_len..];
+        } else {
+            rest = &rest[digest_index..];
+        }
+    }
+    redacted.push_str(rest);
+    Cow::Owned(redacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_signatures() {
+        let signature = format!("$1${}", "0123456789abcdef".repeat(3).split_at(40).0);
+        assert_eq!(
+            redact_signatures(&format!("X-Ovh-Signature: {}, next", signature)),
+            "X-Ovh-Signature: $1$***, next"
+        );
+        assert_eq!(redact_signatures("price $1$ only"), "price $1$ only");
+        assert_eq!(redact_signatures("no signature"), "no signature");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_journald_datagram() {
+        let datagram = journald_datagram(Level::Warn, "ovh_dns_updater::ovh::client", "a\nb");
+        let fields_end = datagram.len() - "MESSAGE\n".len() - 8 - "a\nb\n".len();
+        let fields = String::from_utf8_lossy(&datagram[..fields_end]);
+        assert!(fields.starts_with("PRIORITY=4\nSYSLOG_IDENTIFIER=ovh_dns_updater\n"));
+        assert!(fields.ends_with("CODE_MODULE=ovh_dns_updater::ovh::client\n"));
+        assert!(datagram[fields_end..].starts_with(b"MESSAGE\n\x03\0\0\0\0\0\0\0a\nb\n"));
+    }
+}
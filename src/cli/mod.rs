@@ -0,0 +1,17 @@
+pub(crate) mod auth_command;
+pub(crate) mod check_command;
+pub(crate) mod config_file;
+pub(crate) mod daemon_command;
+pub(crate) mod delete_command;
+pub(crate) mod dry_run;
+pub(crate) mod env_config;
+pub(crate) mod exit_code;
+pub(crate) mod list_command;
+pub(crate) mod logger;
+pub(crate) mod output_format;
+pub(crate) mod prompt;
+pub(crate) mod record_sync;
+pub(crate) mod record_zones;
+pub(crate) mod state_file;
+pub(crate) mod sync_report;
+pub(crate) mod zone_command;
@@ -0,0 +1,12 @@
+//! How the results of a subcommand are printed
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Table,
+    /// JSON, for scripts
+    Json,
+}
@@ -0,0 +1,25 @@
+//! Questions asked to the user on the terminal
+
+use crate::cli::exit_code::ExitCode;
+use log::error;
+use std::io::{IsTerminal, Write};
+
+/// Ask the user a yes/no question on the terminal, no is the default.
+/// Exits with a usage error when nobody can answer, stdin isn't a terminal or is closed, rather than doing nothing silently
+pub(crate) fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        error!("Cannot ask for confirmation, stdin isn't a terminal: use --yes");
+        ExitCode::UsageError.exit();
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().ok();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            println!();
+            error!("No answer to the confirmation");
+            ExitCode::UsageError.exit();
+        }
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+    }
+}
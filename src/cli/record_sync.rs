@@ -0,0 +1,565 @@
+//! Synchronizes A and AAAA records with the public IP addresses,
+//! for the record given on the command line or the records of a config file
+
+use crate::cli::config_file::{ConfigFile, RecordConfig};
+use crate::cli::dry_run::print_planned_requests;
+use crate::cli::env_config::try_create_ovh_dns_updater_from_env_var;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::state_file::StateFile;
+use crate::cli::sync_report::{RecordReport, ReportAction, SyncReport};
+use futures::{future, stream, StreamExt};
+use log::{error, log_enabled, Level};
+use ovh_dns_updater::ovh::planned_request::PlannedRequest;
+use ovh_dns_updater::ovh::retry_policy::RetryPolicy;
+use ovh_dns_updater::ovh_dns_updater::ip_family::IpFamily;
+use ovh_dns_updater::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError::{
+    OvhDomainCreatingError, OvhDomainDeletingError, OvhDomainUpdatingError,
+};
+use ovh_dns_updater::ovh_dns_updater::record_options::{LostAddressPolicy, RecordOptions};
+use ovh_dns_updater::ovh_dns_updater::zone_refresh::{ZoneRefreshPolicy, ZoneRefreshStatus};
+use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
+use ovh_dns_updater::public_ip_retriever::ip_source::IpSource;
+use ovh_dns_updater::public_ip_retriever::IpRetrieverFromFasterService;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Arguments;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Public address of each source and family, with the service that gave it
+type PublicIps = HashMap<(IpSource, IpFamily), Option<(IpAddr, IpSource)>>;
+
+/// Synchronizes the records of a config file, the DNS updaters and the IP retriever are kept between synchronizations
+pub(crate) struct RecordSyncer {
+    config_file: ConfigFile,
+    /// DNS updater of each credentials profile, None for the environment variables
+    ovh_dns_updaters: BTreeMap<Option<String>, OvhDnsUpdater>,
+    ip_retriever: IpRetrieverFromFasterService,
+    state_file: Option<StateFile>,
+    dry_run: bool,
+    output_format: OutputFormat,
+}
+
+impl RecordSyncer {
+    /// Create the DNS updaters of the profiles used by the records
+    /// # Returns
+    /// * `Err(exit_code)` - If a DNS updater can't be created, the error is logged
+    pub(crate) fn new(
+        config_file: ConfigFile,
+        retry_policy: &RetryPolicy,
+        zone_refresh_policy: ZoneRefreshPolicy,
+    ) -> Result<Self, ExitCode> {
+        let mut ovh_dns_updaters = BTreeMap::new();
+        for record_config in &config_file.records {
+            if !ovh_dns_updaters.contains_key(&record_config.profile) {
+                let mut ovh_dns_updater =
+                    create_ovh_dns_updater(&config_file, record_config.profile.as_deref())?;
+                ovh_dns_updater.set_retry_policy(retry_policy.clone());
+                // Records of the same zone are published by a single refresh at the end
+                ovh_dns_updater.set_zone_refresh_policy(zone_refresh_policy);
+                ovh_dns_updaters.insert(record_config.profile.clone(), ovh_dns_updater);
+            }
+        }
+        Ok(Self {
+            config_file,
+            ovh_dns_updaters,
+            ip_retriever: IpRetrieverFromFasterService::new(),
+            state_file: None,
+            dry_run: false,
+            output_format: OutputFormat::default(),
+        })
+    }
+
+    /// Set how often the DNS updaters measure again the time difference with the OVH API server
+    pub(crate) fn set_time_delta_refresh_interval(&mut self, interval: Option<Duration>) {
+        for ovh_dns_updater in self.ovh_dns_updaters.values_mut() {
+            ovh_dns_updater.set_time_delta_refresh_interval(interval);
+        }
+    }
+
+    /// Skip the records whose public IP didn't change since the last synchronization recorded in the state file
+    pub(crate) fn set_state_file(&mut self, state_file: StateFile) {
+        self.state_file = Some(state_file);
+    }
+
+    /// Print the requests that would modify the records instead of sending them, the state file isn't written
+    pub(crate) fn set_dry_run(&mut self, dry_run: bool) {
+        for ovh_dns_updater in self.ovh_dns_updaters.values_mut() {
+            ovh_dns_updater.set_dry_run(dry_run);
+        }
+        self.dry_run = dry_run;
+    }
+
+    /// With `OutputFormat::Json`, a single report is printed at the end of each synchronization instead of the progress
+    pub(crate) fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.output_format = output_format;
+    }
+
+    /// Synchronize all the records, at most `config_file.concurrency` at once, then refresh the modified zones
+    /// # Returns
+    /// * `Err(exit_code)` - `ExitCode::PartialFailure` if only some of the records failed,
+    ///   otherwise the exit code of the first record that failed, in the config file order
+    pub(crate) async fn sync(&self) -> Result<(), ExitCode> {
+        let public_ips = self.retrieve_public_ips().await;
+
+        let mut entry_reports: Vec<Vec<RecordReport>> = stream::iter(&self.config_file.records)
+            .map(|record_config| self.sync_config_record(record_config, &public_ips))
+            .buffered(self.config_file.concurrency)
+            .collect()
+            .await;
+
+        // Publish what has been changed, even if a record failed
+        for (profile, ovh_dns_updater) in &self.ovh_dns_updaters {
+            for (zone, refresh_result) in ovh_dns_updater.refresh_pending_zones().await {
+                let Err(e) = refresh_result else {
+                    if !self.dry_run {
+                        self.print_progress(format_args!("Zone {} refreshed", zone));
+                    }
+                    continue;
+                };
+                error!("Cannot refresh zone {}: {}", zone, e);
+                if let Some(state_file) = &self.state_file {
+                    state_file.forget_zone(&zone);
+                }
+                let zone_record_reports = self
+                    .config_file
+                    .records
+                    .iter()
+                    .zip(&mut entry_reports)
+                    .filter(|(record_config, _)| record_config.profile == *profile)
+                    .flat_map(|(_, record_reports)| record_reports)
+                    .filter(|record_report| record_report.zone.as_deref() == Some(&zone));
+                for record_report in zone_record_reports {
+                    if record_report.is_change() && record_report.error.is_none() {
+                        record_report.set_error(ExitCode::ZoneRefreshError, e.to_string());
+                    }
+                }
+            }
+        }
+        if let Some(state_file) = self.state_file.as_ref().filter(|_| !self.dry_run) {
+            if let Err(e) = state_file.save() {
+                error!(
+                    "Cannot write state file {}: {}",
+                    state_file.path().display(),
+                    e
+                );
+            }
+        }
+
+        let planned_requests: Vec<PlannedRequest> = self
+            .ovh_dns_updaters
+            .values()
+            .flat_map(OvhDnsUpdater::take_planned_requests)
+            .collect();
+        let exit_code = sync_exit_code(&entry_reports);
+        match self.output_format {
+            OutputFormat::Table => print_planned_requests(&planned_requests),
+            OutputFormat::Json => {
+                let sync_report = SyncReport {
+                    dry_run: self.dry_run,
+                    exit_code: exit_code.code(),
+                    status: exit_code,
+                    records: entry_reports.into_iter().flatten().collect(),
+                    planned_requests,
+                };
+                println!("{}", serde_json::to_string_pretty(&sync_report).unwrap());
+            }
+        }
+        match exit_code {
+            ExitCode::Success => Ok(()),
+            exit_code => Err(exit_code),
+        }
+    }
+
+    /// Human readable progress, only printed with `OutputFormat::Table` and without `--quiet`
+    fn print_progress(&self, progress: Arguments) {
+        if self.output_format == OutputFormat::Table && log_enabled!(Level::Info) {
+            println!("{}", progress);
+        }
+    }
+
+    /// Retrieve once each public address needed by the records
+    async fn retrieve_public_ips(&self) -> PublicIps {
+        let mut needed_public_ips = Vec::new();
+        for record_config in &self.config_file.records {
+            for ip_family in record_config.ip_families() {
+                if !needed_public_ips.contains(&(record_config.ip_source, ip_family)) {
+                    needed_public_ips.push((record_config.ip_source, ip_family));
+                }
+            }
+        }
+        needed_public_ips.sort_by_key(|(ip_source, ip_family)| (*ip_family, ip_source.to_string()));
+
+        let retrieved_public_ips = future::join_all(needed_public_ips.iter().map(
+            |(ip_source, ip_family)| async move {
+                match ip_family {
+                    IpFamily::V4 => self
+                        .ip_retriever
+                        .get_ip4_from(*ip_source)
+                        .await
+                        .map(|(ip4, service)| (IpAddr::V4(ip4), service)),
+                    IpFamily::V6 => self
+                        .ip_retriever
+                        .get_ip6_from(*ip_source)
+                        .await
+                        .map(|(ip6, service)| (IpAddr::V6(ip6), service)),
+                }
+            },
+        ))
+        .await;
+
+        let mut public_ips = PublicIps::new();
+        for ((ip_source, ip_family), public_ip) in
+            needed_public_ips.into_iter().zip(retrieved_public_ips)
+        {
+            let source_description = match ip_source {
+                IpSource::Fastest => String::new(),
+                _ => format!(" ({})", ip_source),
+            };
+            match public_ip {
+                Some((public_ip, _)) => self.print_progress(format_args!(
+                    "Actual {}{}: {}",
+                    ip_family, source_description, public_ip
+                )),
+                None => error!(
+                    "Cannot retrieve current {}{}",
+                    ip_family, source_description
+                ),
+            }
+            public_ips.insert((ip_source, ip_family), public_ip);
+        }
+        public_ips
+    }
+
+    /// Synchronize the A then the AAAA record of a config file entry, a failure of one family doesn't stop the other.
+    /// Records whose public IP is the one recorded in the state file aren't looked up on OVH.
+    async fn sync_config_record(
+        &self,
+        record_config: &RecordConfig,
+        public_ips: &PublicIps,
+    ) -> Vec<RecordReport> {
+        let mut record_reports = Vec::new();
+        for ip_family in record_config.ip_families() {
+            let detected_ip = public_ips[&(record_config.ip_source, ip_family)];
+            let public_ip = detected_ip.map(|(public_ip, _)| public_ip);
+            let record_report = RecordReport::new(
+                &record_config.name,
+                ip_family.record_type(),
+                detected_ip.map(|(public_ip, service)| (public_ip, service.to_string())),
+            );
+            let record_options = record_config.record_options(ip_family);
+            let record_key = format!("{} {}", record_config.name, ip_family.record_type());
+            // A record is synchronized again when its options or credentials change
+            let options = format!("{:?} {:?}", record_config.profile, record_options);
+            if let (Some(state_file), Some(public_ip)) = (&self.state_file, public_ip) {
+                if state_file.is_up_to_date(&record_key, public_ip, &options) {
+                    let record_state = state_file.record_state(&record_key).unwrap();
+                    self.print_progress(format_args!(
+                        "{} already synchronized with {}, records {:?}",
+                        record_key, public_ip, record_state.record_ids
+                    ));
+                    record_reports.extend(record_report.skipped(
+                        record_state.zone,
+                        &record_state.record_ids,
+                        public_ip,
+                    ));
+                    continue;
+                }
+            }
+
+            let family_reports = self
+                .sync_ip_record(
+                    &self.ovh_dns_updaters[&record_config.profile],
+                    ip_family,
+                    public_ip,
+                    &record_options,
+                    record_report,
+                )
+                .await;
+            let error_kind = family_reports
+                .iter()
+                .find_map(|family_report| family_report.error.as_ref())
+                .map(|report_error| report_error.kind);
+            if let Some(state_file) = &self.state_file {
+                match error_kind {
+                    None => state_file.record_synced(
+                        &record_key,
+                        public_ip,
+                        family_reports[0].zone.clone(),
+                        family_reports
+                            .iter()
+                            .filter(|family_report| family_report.action != ReportAction::Deleted)
+                            .filter_map(|family_report| family_report.record_id)
+                            .collect(),
+                        &options,
+                    ),
+                    // The record hasn't been touched when the address is missing and kept
+                    Some(ExitCode::PublicIpUnavailable) => (),
+                    Some(_) => state_file.forget(&record_key),
+                }
+            }
+            record_reports.extend(family_reports);
+        }
+        record_reports
+    }
+
+    /// Synchronize the record of the given family with the public address
+    /// # Returns
+    /// The report of each DNS record, or the given report if there is none or the synchronization failed.
+    /// The error kind is `PublicIpUnavailable` if the address couldn't be retrieved and the record is kept,
+    /// `OvhReadError` if the record can't be retrieved, `OvhWriteError` if the update failed
+    async fn sync_ip_record(
+        &self,
+        ovh_dns_updater: &OvhDnsUpdater,
+        ip_family: IpFamily,
+        public_ip: Option<IpAddr>,
+        record_options: &RecordOptions,
+        mut record_report: RecordReport,
+    ) -> Vec<RecordReport> {
+        let record_to_update = &record_report.record;
+        let update_outcomes = match ovh_dns_updater
+            .sync_record(record_to_update, ip_family, public_ip, record_options)
+            .await
+        {
+            Ok(update_outcomes) => update_outcomes,
+            Err(e) => {
+                error!(
+                    "Cannot update {} record {}: {}",
+                    ip_family, record_to_update, e
+                );
+                let error_kind = match e {
+                    OvhDomainUpdatingError(_)
+                    | OvhDomainCreatingError(_)
+                    | OvhDomainDeletingError(_) => ExitCode::OvhWriteError,
+                    _ => ExitCode::OvhReadError,
+                };
+                record_report.action = ReportAction::Failed;
+                record_report.set_error(error_kind, e.to_string());
+                return vec![record_report];
+            }
+        };
+        if update_outcomes.is_empty() {
+            self.print_progress(format_args!(
+                "No {} record for {}",
+                ip_family, record_to_update
+            ));
+        }
+        // In dry run, the planned requests are the only report of the changes
+        for update_outcome in update_outcomes
+            .iter()
+            .filter(|update_outcome| !self.dry_run || !update_outcome.is_change())
+        {
+            self.print_progress(format_args!(
+                "{} ({:.2?})",
+                update_outcome, update_outcome.duration
+            ));
+        }
+        // All the changes of a synchronization share the same zone refresh
+        let mut zone_refresh_error = None;
+        if let Some(update_outcome) = update_outcomes
+            .iter()
+            .find(|o| o.is_change())
+            .filter(|_| !self.dry_run)
+        {
+            match &update_outcome.zone_refresh {
+                ZoneRefreshStatus::Refreshed => {
+                    self.print_progress(format_args!("Zone {} refreshed", update_outcome.zone))
+                }
+                ZoneRefreshStatus::Skipped => self
+                    .print_progress(format_args!("Zone {} refresh skipped", update_outcome.zone)),
+                ZoneRefreshStatus::Failed(e) => {
+                    error!("Cannot refresh zone {}: {}", update_outcome.zone, e);
+                    zone_refresh_error = Some(e.to_string());
+                }
+                ZoneRefreshStatus::Pending | ZoneRefreshStatus::NotNeeded => (),
+            }
+        }
+
+        let mut record_reports = if update_outcomes.is_empty() {
+            vec![record_report]
+        } else {
+            update_outcomes
+                .iter()
+                .map(|update_outcome| record_report.clone().with_outcome(update_outcome))
+                .collect()
+        };
+        for record_report in &mut record_reports {
+            if public_ip.is_none() && record_options.lost_address == LostAddressPolicy::Keep {
+                record_report.set_error(
+                    ExitCode::PublicIpUnavailable,
+                    format!("Cannot retrieve current {}", ip_family),
+                );
+            } else if let Some(e) = zone_refresh_error
+                .as_ref()
+                .filter(|_| record_report.is_change())
+            {
+                record_report.set_error(ExitCode::ZoneRefreshError, e.clone());
+            }
+        }
+        record_reports
+    }
+}
+
+/// The exit code of a synchronization, from the reports of each config file entry.
+/// The records of each IP family are counted apart, an entry may be partially synchronized.
+fn sync_exit_code(entry_reports: &[Vec<RecordReport>]) -> ExitCode {
+    let record_errors: Vec<Option<ExitCode>> = entry_reports
+        .iter()
+        .flatten()
+        .map(|record_report| {
+            record_report
+                .error
+                .as_ref()
+                .map(|report_error| report_error.kind)
+        })
+        .collect();
+    match record_errors.iter().flatten().next() {
+        None => ExitCode::Success,
+        Some(_) if record_errors.iter().any(Option::is_none) => ExitCode::PartialFailure,
+        Some(first_error) => *first_error,
+    }
+}
+
+/// Creates the DNS updater of the given profile, from the environment variables if there is none
+/// # Returns
+/// * `Err(exit_code)` - If an environment variable is missing or invalid, the error is logged
+fn create_ovh_dns_updater(
+    config_file: &ConfigFile,
+    profile: Option<&str>,
+) -> Result<OvhDnsUpdater, ExitCode> {
+    let Some(profile) = profile else {
+        return try_create_ovh_dns_updater_from_env_var();
+    };
+    // Profiles are checked when the config file is read
+    let credentials = &config_file.profiles[profile];
+    OvhDnsUpdater::new(
+        &credentials.endpoint,
+        &credentials.application_key,
+        &credentials.application_secret,
+        &credentials.consumer_key,
+    )
+    .map_err(|e| {
+        error!(
+            "Error when creating OVH DNS updater of profile {}: {}",
+            profile, e
+        );
+        ExitCode::UsageError
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ovh_dns_updater::ovh_dns_updater::dns_record::DnsRecordType;
+    use std::path::Path;
+
+    #[test]
+    fn test_sync_exit_code() {
+        let succeeded = RecordReport::new("home.example.com", DnsRecordType::A, None);
+        let mut failed = succeeded.clone();
+        failed.set_error(ExitCode::OvhWriteError, "error".to_string());
+        let mut unavailable = succeeded.clone();
+        unavailable.set_error(ExitCode::PublicIpUnavailable, "error".to_string());
+
+        assert_eq!(
+            sync_exit_code(&[vec![succeeded.clone()], vec![succeeded.clone()]]),
+            ExitCode::Success
+        );
+        assert_eq!(
+            sync_exit_code(&[vec![failed.clone()], vec![failed.clone()]]),
+            ExitCode::OvhWriteError
+        );
+        assert_eq!(
+            sync_exit_code(&[vec![unavailable.clone()], vec![failed.clone()]]),
+            ExitCode::PublicIpUnavailable
+        );
+        assert_eq!(
+            sync_exit_code(&[vec![failed], vec![succeeded]]),
+            ExitCode::PartialFailure
+        );
+    }
+
+    #[test]
+    fn test_sync_exit_code_of_partially_synchronized_entry() {
+        let mut ipv4_failed = RecordReport::new("home.example.com", DnsRecordType::A, None);
+        ipv4_failed.set_error(ExitCode::OvhWriteError, "error".to_string());
+        let ipv6_updated = RecordReport::new("home.example.com", DnsRecordType::Aaaa, None);
+
+        assert_eq!(
+            sync_exit_code(&[vec![ipv6_updated.clone(), ipv4_failed.clone()]]),
+            ExitCode::PartialFailure
+        );
+        assert_eq!(
+            sync_exit_code(&[vec![ipv4_failed.clone()], vec![ipv6_updated, ipv4_failed]]),
+            ExitCode::PartialFailure
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_config_record_continues_after_failure() {
+        // Nothing listens on the discard port, every OVH request fails at once
+        let config_file = ConfigFile::from_toml(
+            r#"
+            [profile.local]
+            endpoint = "http://127.0.0.1:9/1.0"
+            application_key = "key"
+            application_secret = "secret"
+            consumer_key = "consumer"
+
+            [[record]]
+            name = "home.example.com"
+            ipv6 = true
+            profile = "local"
+            "#,
+        )
+        .unwrap();
+        let record_config = config_file.records[0].clone();
+        let mut record_syncer = RecordSyncer::new(
+            config_file,
+            &RetryPolicy::no_retry(),
+            ZoneRefreshPolicy::Deferred,
+        )
+        .unwrap();
+        // The AAAA record is already synchronized, it doesn't need OVH
+        let ipv6: IpAddr = "2001:db8::1".parse().unwrap();
+        let state_file = StateFile::load(
+            Path::new("/nonexistent/ovh_dns_updater_state.json"),
+            Duration::from_secs(3600),
+        );
+        state_file.record_synced(
+            "home.example.com AAAA",
+            Some(ipv6),
+            Some("example.com".to_string()),
+            vec![2],
+            &format!(
+                "{:?} {:?}",
+                record_config.profile,
+                record_config.record_options(IpFamily::V6)
+            ),
+        );
+        record_syncer.set_state_file(state_file);
+        let public_ips = PublicIps::from([
+            (
+                (IpSource::Fastest, IpFamily::V4),
+                Some(("1.2.3.4".parse().unwrap(), IpSource::Fastest)),
+            ),
+            (
+                (IpSource::Fastest, IpFamily::V6),
+                Some((ipv6, IpSource::Fastest)),
+            ),
+        ]);
+
+        let record_reports = record_syncer
+            .sync_config_record(&record_config, &public_ips)
+            .await;
+        assert_eq!(record_reports.len(), 2);
+        assert_eq!(record_reports[0].record_type, DnsRecordType::A);
+        assert_eq!(
+            record_reports[0].error.as_ref().map(|e| e.kind),
+            Some(ExitCode::OvhReadError)
+        );
+        assert_eq!(record_reports[1].record_type, DnsRecordType::Aaaa);
+        assert!(record_reports[1].error.is_none());
+        assert_eq!(record_reports[1].record_id, Some(2));
+    }
+}
@@ -0,0 +1,79 @@
+//! DNS zones of the records given to the credential commands, on the command line or in a config file
+
+use crate::cli::config_file::read_config_file;
+use crate::cli::exit_code::ExitCode;
+use clap::Args;
+use log::{error, warn};
+use ovh_dns_updater::ovh_dns_updater::ip_family::IpFamily;
+use ovh_dns_updater::ovh_dns_updater::record_options::RecordOptions;
+use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub(crate) struct RecordZoneArgs {
+    /// DNS zone of the records given with --record, when it isn't the registered domain, e.g. a delegated sub-zone
+    #[arg(long)]
+    zone: Option<String>,
+    /// Find the zone of the records given with --record among the DNS zones of the OVH account
+    #[arg(long, conflicts_with = "zone")]
+    discover_zone: bool,
+    /// TOML config file whose records are also used, with their zone. Records of another profile are ignored
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+impl RecordZoneArgs {
+    /// The given records and those of the config file using the environment variables credentials,
+    /// with their zone options. Exits if the config file is invalid
+    pub(crate) fn records(&self, command_line_records: &[String]) -> Vec<(String, RecordOptions)> {
+        let mut records: Vec<(String, RecordOptions)> = command_line_records
+            .iter()
+            .map(|record| {
+                let record_options = RecordOptions {
+                    zone: self.zone.clone(),
+                    discover_zone: self.discover_zone,
+                    ..RecordOptions::default()
+                };
+                (record.clone(), record_options)
+            })
+            .collect();
+        if let Some(config_path) = &self.config {
+            for record_config in read_config_file(config_path).records {
+                if let Some(profile) = &record_config.profile {
+                    warn!(
+                        "Record {} ignored, it uses the credentials of profile {}",
+                        record_config.name, profile
+                    );
+                    continue;
+                }
+                let record_options = record_config.record_options(IpFamily::V4);
+                records.push((record_config.name, record_options));
+            }
+        }
+        records
+    }
+}
+
+/// The zones of the records, found like when they are updated, exits on failure
+pub(crate) async fn resolve_zones(
+    records: &[(String, RecordOptions)],
+    ovh_dns_updater: &OvhDnsUpdater,
+) -> BTreeSet<String> {
+    let mut zones = BTreeSet::new();
+    for (record, record_options) in records {
+        match ovh_dns_updater
+            .resolve_record_zone(record, record_options)
+            .await
+        {
+            Ok((zone, _)) => {
+                zones.insert(zone);
+            }
+            Err(e) => {
+                error!("Cannot find the zone of record {}: {}", record, e);
+                ExitCode::SetupError.exit();
+            }
+        }
+    }
+    zones
+}
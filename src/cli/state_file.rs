@@ -0,0 +1,214 @@
+//! State file remembering the address published in each record, to skip the OVH API calls while the public IP doesn't change
+
+use clap::Args;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Once a day by default, so that changes made to the records on OVH are eventually overwritten
+const DEFAULT_RESYNC_INTERVAL: u64 = 24 * 3600;
+
+#[derive(Args, Debug)]
+pub(crate) struct StateFileArgs {
+    /// JSON file remembering the address published in each record, OVH isn't called while the public IP doesn't change
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+    /// Seconds after which records are synchronized with OVH again, even if the public IP didn't change
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_RESYNC_INTERVAL, requires = "state_file")]
+    resync_interval: u64,
+}
+
+impl StateFileArgs {
+    /// Load the state file, if one is given
+    pub(crate) fn load_state_file(&self) -> Option<StateFile> {
+        self.state_file
+            .as_ref()
+            .map(|path| StateFile::load(path, Duration::from_secs(self.resync_interval)))
+    }
+}
+
+/// Content of the state file, records are identified by their name and type, e.g. "home.example.com AAAA"
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct SyncState {
+    records: BTreeMap<String, RecordState>,
+}
+
+/// What was published in a record at the last synchronization
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct RecordState {
+    /// The public address the record was synchronized with, None if it couldn't be retrieved
+    pub(crate) ip: Option<IpAddr>,
+    /// DNS zone of the record, None if there is no record
+    pub(crate) zone: Option<String>,
+    /// The OVH ids of the records holding the address
+    pub(crate) record_ids: Vec<usize>,
+    /// The options of the record, a record whose options changed is synchronized again
+    pub(crate) options: String,
+    /// Seconds since the Unix epoch
+    pub(crate) synced_at: u64,
+}
+
+pub(crate) struct StateFile {
+    path: PathBuf,
+    /// Records are synchronized with OVH at least this often, even if the public IP didn't change
+    resync_interval: Duration,
+    state: Mutex<SyncState>,
+    /// The state as last read or written on disk
+    saved_state: Mutex<SyncState>,
+}
+
+impl StateFile {
+    /// Read the state file, a missing or unreadable file gives an empty state as every record is then synchronized
+    pub(crate) fn load(path: &Path, resync_interval: Duration) -> Self {
+        let state = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid state file {}: {}", path.display(), e);
+                SyncState::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SyncState::default(),
+            Err(e) => {
+                warn!("Ignoring unreadable state file {}: {}", path.display(), e);
+                SyncState::default()
+            }
+        };
+        Self {
+            path: path.to_path_buf(),
+            resync_interval,
+            state: Mutex::new(state.clone()),
+            saved_state: Mutex::new(state),
+        }
+    }
+
+    /// Whether the record already holds the given address, with the same options, and was synchronized recently enough
+    pub(crate) fn is_up_to_date(&self, record_key: &str, ip: IpAddr, options: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state.records.get(record_key).is_some_and(|record_state| {
+            record_state.ip == Some(ip)
+                && record_state.options == options
+                && unix_time().saturating_sub(record_state.synced_at)
+                    < self.resync_interval.as_secs()
+        })
+    }
+
+    pub(crate) fn record_state(&self, record_key: &str) -> Option<RecordState> {
+        self.state.lock().unwrap().records.get(record_key).cloned()
+    }
+
+    /// Remember what has just been published in the record
+    pub(crate) fn record_synced(
+        &self,
+        record_key: &str,
+        ip: Option<IpAddr>,
+        zone: Option<String>,
+        record_ids: Vec<usize>,
+        options: &str,
+    ) {
+        self.state.lock().unwrap().records.insert(
+            record_key.to_string(),
+            RecordState {
+                ip,
+                zone,
+                record_ids,
+                options: options.to_string(),
+                synced_at: unix_time(),
+            },
+        );
+    }
+
+    /// Forget a record whose synchronization failed, so that it is synchronized at the next run
+    pub(crate) fn forget(&self, record_key: &str) {
+        self.state.lock().unwrap().records.remove(record_key);
+    }
+
+    /// Forget the records of a zone whose refresh failed, so that the changes are published at the next run
+    pub(crate) fn forget_zone(&self, zone: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .records
+            .retain(|_, record_state| record_state.zone.as_deref() != Some(zone));
+    }
+
+    /// Write the state if it changed, to a temporary file renamed over the state file,
+    /// so that an interrupted run never leaves a truncated state file
+    pub(crate) fn save(&self) -> io::Result<()> {
+        let state = self.state.lock().unwrap().clone();
+        let mut saved_state = self.saved_state.lock().unwrap();
+        if state == *saved_state {
+            return Ok(());
+        }
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        let content = serde_json::to_string_pretty(&state).map_err(io::Error::other)?;
+        std::fs::write(&temporary_path, content)?;
+        std::fs::rename(&temporary_path, &self.path)?;
+        *saved_state = state;
+        Ok(())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_file() {
+        let path =
+            std::env::temp_dir().join(format!("ovh_dns_updater_state_{}.json", std::process::id()));
+        let ip: IpAddr = "1.1.1.1".parse().unwrap();
+
+        let state_file = StateFile::load(&path, Duration::from_secs(3600));
+        assert!(!state_file.is_up_to_date("home.example.com A", ip, "options"));
+        state_file.record_synced(
+            "home.example.com A",
+            Some(ip),
+            Some("example.com".to_string()),
+            vec![1],
+            "options",
+        );
+        assert!(state_file.is_up_to_date("home.example.com A", ip, "options"));
+        assert!(!state_file.is_up_to_date("home.example.com A", ip, "other options"));
+        assert!(!state_file.is_up_to_date(
+            "home.example.com A",
+            "2.2.2.2".parse().unwrap(),
+            "options"
+        ));
+        state_file.save().unwrap();
+
+        let state_file = StateFile::load(&path, Duration::from_secs(3600));
+        assert_eq!(
+            state_file
+                .record_state("home.example.com A")
+                .unwrap()
+                .record_ids,
+            vec![1]
+        );
+        assert!(state_file.is_up_to_date("home.example.com A", ip, "options"));
+        state_file.forget_zone("example.com");
+        assert!(!state_file.is_up_to_date("home.example.com A", ip, "options"));
+        state_file.save().unwrap();
+
+        // A forced resync is due at once
+        let state_file = StateFile::load(&path, Duration::ZERO);
+        assert!(state_file.record_state("home.example.com A").is_none());
+        state_file.record_synced("home.example.com A", Some(ip), None, vec![], "options");
+        assert!(!state_file.is_up_to_date("home.example.com A", ip, "options"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
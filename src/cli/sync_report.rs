@@ -0,0 +1,144 @@
+//! Result of a synchronization, printed with `--output json` for scripts and monitoring
+
+use crate::cli::exit_code::ExitCode;
+use ovh_dns_updater::ovh::planned_request::PlannedRequest;
+use ovh_dns_updater::ovh_dns_updater::dns_record::DnsRecordType;
+use ovh_dns_updater::ovh_dns_updater::record_action::RecordAction;
+use ovh_dns_updater::ovh_dns_updater::update_outcome::UpdateOutcome;
+use serde::Serialize;
+use std::net::IpAddr;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct SyncReport {
+    pub(crate) dry_run: bool,
+    pub(crate) exit_code: i32,
+    /// Name of the exit code, e.g. "partial_failure"
+    pub(crate) status: ExitCode,
+    pub(crate) records: Vec<RecordReport>,
+    /// Requests that the dry run didn't send, empty otherwise
+    pub(crate) planned_requests: Vec<PlannedRequest>,
+}
+
+/// Synchronization of a DNS record, or of a record name and type when there is no record or it failed
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct RecordReport {
+    /// The full domain name, e.g. "home.example.com"
+    pub(crate) record: String,
+    #[serde(rename = "type")]
+    pub(crate) record_type: DnsRecordType,
+    pub(crate) zone: Option<String>,
+    pub(crate) record_id: Option<usize>,
+    /// The public address, None if it couldn't be retrieved
+    pub(crate) detected_ip: Option<IpAddr>,
+    /// The service that gave the public address, e.g. "ipify"
+    pub(crate) ip_source: Option<String>,
+    /// Target of the record before the synchronization
+    pub(crate) recorded_ip: Option<String>,
+    /// Target of the record after the synchronization, None if it was deleted
+    pub(crate) published_ip: Option<String>,
+    pub(crate) action: ReportAction,
+    pub(crate) error: Option<ReportError>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReportAction {
+    Created,
+    Updated,
+    Unchanged,
+    Deleted,
+    /// The state file shows the record already holds the public address, OVH wasn't called
+    Skipped,
+    /// There is no record and it isn't created
+    Missing,
+    /// The record couldn't be synchronized
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct ReportError {
+    /// Name of the exit code of the failure, e.g. "ovh_write_error"
+    pub(crate) kind: ExitCode,
+    pub(crate) message: String,
+}
+
+impl From<RecordAction> for ReportAction {
+    fn from(record_action: RecordAction) -> Self {
+        match record_action {
+            RecordAction::Created => ReportAction::Created,
+            RecordAction::Updated => ReportAction::Updated,
+            RecordAction::Unchanged => ReportAction::Unchanged,
+            RecordAction::Deleted => ReportAction::Deleted,
+        }
+    }
+}
+
+impl RecordReport {
+    /// Report of a record name and type without DNS record, the action and OVH fields are to be filled
+    pub(crate) fn new(
+        record: &str,
+        record_type: DnsRecordType,
+        detected_ip: Option<(IpAddr, String)>,
+    ) -> Self {
+        let (detected_ip, ip_source) = detected_ip.unzip();
+        Self {
+            record: record.to_string(),
+            record_type,
+            zone: None,
+            record_id: None,
+            detected_ip,
+            ip_source,
+            recorded_ip: None,
+            published_ip: None,
+            action: ReportAction::Missing,
+            error: None,
+        }
+    }
+
+    /// Report of a DNS record the updater looked up or modified
+    pub(crate) fn with_outcome(mut self, update_outcome: &UpdateOutcome) -> Self {
+        self.zone = Some(update_outcome.zone.clone());
+        self.record_id = update_outcome.record_id;
+        self.recorded_ip = update_outcome.old_target.clone();
+        self.published_ip = update_outcome.new_target.clone();
+        self.action = update_outcome.action.into();
+        self
+    }
+
+    /// Report of the DNS records left untouched because the state file shows they hold the public address
+    pub(crate) fn skipped(
+        self,
+        zone: Option<String>,
+        record_ids: &[usize],
+        public_ip: IpAddr,
+    ) -> Vec<Self> {
+        let skipped_report = Self {
+            zone,
+            recorded_ip: Some(public_ip.to_string()),
+            published_ip: Some(public_ip.to_string()),
+            action: ReportAction::Skipped,
+            ..self
+        };
+        if record_ids.is_empty() {
+            return vec![skipped_report];
+        }
+        record_ids
+            .iter()
+            .map(|record_id| Self {
+                record_id: Some(*record_id),
+                ..skipped_report.clone()
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_error(&mut self, kind: ExitCode, message: String) {
+        self.error = Some(ReportError { kind, message });
+    }
+
+    pub(crate) fn is_change(&self) -> bool {
+        matches!(
+            self.action,
+            ReportAction::Created | ReportAction::Updated | ReportAction::Deleted
+        )
+    }
+}
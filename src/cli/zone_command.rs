@@ -0,0 +1,157 @@
+//! `plan` and `apply` subcommands: bring DNS zones to the state described in a TOML file
+
+use crate::cli::dry_run::{print_dry_run_notice, print_planned_requests};
+use crate::cli::env_config::create_ovh_dns_updater_from_env_var;
+use crate::cli::exit_code::ExitCode;
+use crate::cli::prompt::confirm;
+use clap::Args;
+use log::error;
+use ovh_dns_updater::ovh_dns_updater::zone_plan::ZonePlan;
+use ovh_dns_updater::ovh_dns_updater::zone_refresh::ZoneRefreshPolicy;
+use ovh_dns_updater::ovh_dns_updater::zone_state::ZoneStateFile;
+use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
+use ovh_dns_updater::public_ip_retriever::IpRetrieverFromFasterService;
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Debug)]
+pub(crate) struct PlanArgs {
+    /// TOML file describing the desired records of the zones
+    #[arg(long, short)]
+    file: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ApplyArgs {
+    /// TOML file describing the desired records of the zones
+    #[arg(long, short)]
+    file: PathBuf,
+    /// Apply the changes without asking for confirmation
+    #[arg(long, short)]
+    yes: bool,
+    /// Compute the changes, but only print the requests that would apply them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub(crate) async fn run_plan_command(plan_args: &PlanArgs) {
+    let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    let zone_plans = plan_zones(&ovh_dns_updater, &plan_args.file).await;
+    print_zone_plans(&zone_plans);
+}
+
+pub(crate) async fn run_apply_command(apply_args: &ApplyArgs) {
+    let mut ovh_dns_updater = create_ovh_dns_updater_from_env_var();
+    // Each zone is refreshed once, after all its changes
+    ovh_dns_updater.set_zone_refresh_policy(ZoneRefreshPolicy::Deferred);
+    if apply_args.dry_run {
+        print_dry_run_notice();
+        ovh_dns_updater.set_dry_run(true);
+    }
+    let zone_plans = plan_zones(&ovh_dns_updater, &apply_args.file).await;
+    print_zone_plans(&zone_plans);
+
+    let changes_count: usize = zone_plans
+        .iter()
+        .map(|zone_plan| zone_plan.changes.len())
+        .sum();
+    if changes_count == 0 {
+        return;
+    }
+    // Nothing is applied in dry run, there is nothing to confirm
+    if !apply_args.yes
+        && !apply_args.dry_run
+        && !confirm(&format!("Apply {} changes?", changes_count))
+    {
+        println!("Nothing applied");
+        return;
+    }
+
+    let mut exit_code = ExitCode::Success;
+    for zone_plan in &zone_plans {
+        // The refresh is deferred, its errors are reported below
+        match ovh_dns_updater.apply_zone_plan(zone_plan).await {
+            // In dry run, the planned requests are the only report of the changes
+            Ok(_) if apply_args.dry_run => (),
+            Ok(_) => println!("Zone {}: changes applied", zone_plan.zone),
+            Err(e) => {
+                error!("Cannot apply changes to zone {}: {}", zone_plan.zone, e);
+                exit_code = ExitCode::OvhWriteError;
+            }
+        }
+    }
+    for (zone, refresh_result) in ovh_dns_updater.refresh_pending_zones().await {
+        match refresh_result {
+            Ok(()) if apply_args.dry_run => (),
+            Ok(()) => println!("Zone {} refreshed", zone),
+            Err(e) => {
+                error!("Cannot refresh zone {}: {}", zone, e);
+                if exit_code == ExitCode::Success {
+                    exit_code = ExitCode::ZoneRefreshError;
+                }
+            }
+        }
+    }
+    print_planned_requests(&ovh_dns_updater.take_planned_requests());
+    if exit_code != ExitCode::Success {
+        exit_code.exit();
+    }
+}
+
+/// Read the zone state file and compute the plan of each zone, exits on failure
+async fn plan_zones(ovh_dns_updater: &OvhDnsUpdater, file: &Path) -> Vec<ZonePlan> {
+    let zone_state_file = match std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|content| ZoneStateFile::from_toml(&content).map_err(|e| e.to_string()))
+    {
+        Ok(zone_state_file) => zone_state_file,
+        Err(e) => {
+            error!("Cannot read zone state file {}: {}", file.display(), e);
+            ExitCode::InvalidConfigFile.exit();
+        }
+    };
+
+    let ip_retriever = IpRetrieverFromFasterService::new();
+    let mut public_ipv4 = None;
+    if zone_state_file.needs_public_ipv4() {
+        public_ipv4 = ip_retriever.get_ip4().await;
+        if public_ipv4.is_none() {
+            error!("Cannot retrieve current IPv4");
+            ExitCode::PublicIpUnavailable.exit();
+        }
+    }
+    let mut public_ipv6 = None;
+    if zone_state_file.needs_public_ipv6() {
+        public_ipv6 = ip_retriever.get_ip6().await;
+        if public_ipv6.is_none() {
+            error!("Cannot retrieve current IPv6");
+            ExitCode::PublicIpUnavailable.exit();
+        }
+    }
+
+    let mut zone_plans = Vec::with_capacity(zone_state_file.zones.len());
+    for zone_state in &zone_state_file.zones {
+        match ovh_dns_updater
+            .plan_zone(zone_state, public_ipv4, public_ipv6)
+            .await
+        {
+            Ok(zone_plan) => zone_plans.push(zone_plan),
+            Err(e) => {
+                error!("Cannot plan changes of zone {}: {}", zone_state.name, e);
+                ExitCode::OvhReadError.exit();
+            }
+        }
+    }
+    zone_plans
+}
+
+fn print_zone_plans(zone_plans: &[ZonePlan]) {
+    for zone_plan in zone_plans {
+        println!("Zone {}:", zone_plan.zone);
+        if zone_plan.is_empty() {
+            println!("  no change");
+        }
+        for record_change in &zone_plan.changes {
+            println!("  {}", record_change);
+        }
+    }
+}
@@ -1,3 +1,4 @@
-mod ovh;
+pub mod address_watcher;
+pub mod ovh;
 pub mod ovh_dns_updater;
 pub mod public_ip_retriever;
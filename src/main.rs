@@ -1,157 +1,216 @@
-use clap::Parser;
-use ovh_dns_updater::ovh_dns_updater::OvhDnsUpdater;
-use ovh_dns_updater::public_ip_retriever::IpRetrieverFromFasterService;
-use std::env;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use cli::auth_command::{run_auth_command, AuthCommand};
+use cli::check_command::{run_check_command, CheckArgs};
+use cli::config_file::{read_config_file, ConfigFile, RecordConfig};
+use cli::daemon_command::{run_daemon_command, DaemonArgs};
+use cli::delete_command::{run_delete_command, DeleteArgs};
+use cli::dry_run::print_dry_run_notice;
+use cli::list_command::{run_records_command, run_zones_command, RecordsArgs, ZonesArgs};
+use cli::logger::LogArgs;
+use cli::output_format::OutputFormat;
+use cli::record_sync::RecordSyncer;
+use cli::state_file::StateFileArgs;
+use cli::zone_command::{run_apply_command, run_plan_command, ApplyArgs, PlanArgs};
+use ovh_dns_updater::ovh::retry_policy::RetryPolicy;
+use ovh_dns_updater::ovh_dns_updater::record_options::{
+    DuplicateRecordPolicy, LostAddressPolicy, TtlPolicy,
+};
+use ovh_dns_updater::ovh_dns_updater::zone_refresh::ZoneRefreshPolicy;
+use ovh_dns_updater::public_ip_retriever::ip_source::IpSource;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+mod cli;
 
 #[tokio::main]
 async fn main() {
     let cmd_args = Args::parse();
-
-    if !cmd_args.no_ip4 {
-        let ip_retriever = IpRetrieverFromFasterService::new();
-        let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
-        manage_ip4_record_upgrade(&ip_retriever, &ovh_dns_updater, &cmd_args.record).await;
-    }
-    if cmd_args.upgrade_ip6 {
-        let ip_retriever = IpRetrieverFromFasterService::new();
-        let ovh_dns_updater = create_ovh_dns_updater_from_env_var();
-        manage_ip6_record_upgrade(&ip_retriever, &ovh_dns_updater, &cmd_args.record).await;
+    cmd_args.log_args.init_logger();
+    match &cmd_args.command {
+        Some(Command::Auth { command }) => run_auth_command(command).await,
+        Some(Command::Check(check_args)) => run_check_command(check_args).await,
+        Some(Command::Plan(plan_args)) => run_plan_command(plan_args).await,
+        Some(Command::Apply(apply_args)) => run_apply_command(apply_args).await,
+        Some(Command::Zones(zones_args)) => run_zones_command(zones_args).await,
+        Some(Command::Records(records_args)) => run_records_command(records_args).await,
+        Some(Command::Delete(delete_args)) => run_delete_command(delete_args).await,
+        Some(Command::Daemon(daemon_args)) => run_daemon_command(daemon_args).await,
+        None => update_record(&cmd_args).await,
     }
 }
 
-fn create_ovh_dns_updater_from_env_var() -> OvhDnsUpdater {
-    dotenv::dotenv().ok();
-    let endpoint = match env::var("OVH_ENDPOINT") {
-        Ok(endpoint) => endpoint,
-        Err(_) => {
-            eprintln!("OVH_ENDPOINT env variable must be set");
-            std::process::exit(1);
-        }
-    };
-    let ovh_application_key = match env::var("OVH_APPLICATION_KEY") {
-        Ok(ovh_application_key) => ovh_application_key,
-        Err(_) => {
-            eprintln!("OVH_APPLICATION_KEY env variable must be set");
-            std::process::exit(1);
+async fn update_record(cmd_args: &Args) {
+    let config_file = match &cmd_args.config {
+        Some(config_path) => read_config_file(config_path),
+        None => {
+            check_fallback_address_family(&cmd_args.on_ip4_lost, "--on-ip4-lost", IpAddr::is_ipv4);
+            check_fallback_address_family(&cmd_args.on_ip6_lost, "--on-ip6-lost", IpAddr::is_ipv6);
+            ConfigFile {
+                concurrency: 1,
+                profiles: BTreeMap::new(),
+                records: vec![RecordConfig {
+                    // Required unless a subcommand or a config file is used
+                    name: cmd_args.record.clone().unwrap(),
+                    ipv4: !cmd_args.no_ip4,
+                    ipv6: cmd_args.upgrade_ip6,
+                    ttl: cmd_args.ttl,
+                    zone: cmd_args.zone.clone(),
+                    discover_zone: cmd_args.discover_zone,
+                    profile: None,
+                    ip_source: cmd_args.ip_source,
+                    create: cmd_args.create,
+                    duplicates: cmd_args.duplicates,
+                    on_ip4_lost: cmd_args.on_ip4_lost,
+                    on_ip6_lost: cmd_args.on_ip6_lost,
+                }],
+            }
         }
     };
-    let ovh_application_secret = match env::var("OVH_APPLICATION_SECRET") {
-        Ok(ovh_application_secret) => ovh_application_secret,
-        Err(_) => {
-            eprintln!("OVH_APPLICATION_SECRET env variable must be set");
-            std::process::exit(1);
-        }
+    let retry_policy = RetryPolicy {
+        max_retries: cmd_args.retries,
+        ..RetryPolicy::default()
     };
-    let ovh_consumer_key = match env::var("OVH_CONSUMER_KEY") {
-        Ok(ovh_consumer_key) => ovh_consumer_key,
-        Err(_) => {
-            eprintln!("OVH_CONSUMER_KEY env variable must be set");
-            std::process::exit(1);
-        }
+    let zone_refresh_policy = if cmd_args.no_zone_refresh {
+        ZoneRefreshPolicy::Skip
+    } else {
+        ZoneRefreshPolicy::Deferred
     };
-    match OvhDnsUpdater::new(
-        &endpoint,
-        &ovh_application_key,
-        &ovh_application_secret,
-        &ovh_consumer_key,
-    ) {
-        Ok(ovh_dns_updater) => ovh_dns_updater,
-        Err(e) => {
-            eprintln!("Error when creating OVH DNS updater: {:?}", e);
-            std::process::exit(2);
+    let mut record_syncer = RecordSyncer::new(config_file, &retry_policy, zone_refresh_policy)
+        .unwrap_or_else(|exit_code| exit_code.exit());
+    if cmd_args.dry_run {
+        if cmd_args.output == OutputFormat::Table {
+            print_dry_run_notice();
         }
+        record_syncer.set_dry_run(true);
+    }
+    record_syncer.set_output_format(cmd_args.output);
+    if let Some(state_file) = cmd_args.state_file_args.load_state_file() {
+        record_syncer.set_state_file(state_file);
+    }
+    if let Err(exit_code) = record_syncer.sync().await {
+        exit_code.exit();
     }
 }
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(long, short)]
     no_ip4: bool,
     #[arg(long, short = '6')]
     upgrade_ip6: bool,
+    #[arg(long, short, required_unless_present = "config")]
+    record: Option<String>,
+    /// TOML file listing the records to update, with their options, instead of a single record
+    #[arg(long, conflicts_with_all = [
+        "record", "no_ip4", "upgrade_ip6", "create", "ttl", "duplicates", "zone",
+        "discover_zone", "on_ip4_lost", "on_ip6_lost", "ip_source",
+    ])]
+    config: Option<PathBuf>,
+    /// Create the record if it doesn't exist yet
     #[arg(long, short)]
-    record: String,
+    create: bool,
+    /// TTL of the record: "preserve" keeps the current TTL, "default" uses the zone default TTL, or a number of seconds
+    #[arg(long, default_value_t = TtlPolicy::default())]
+    ttl: TtlPolicy,
+    /// Don't refresh the DNS zone after a change, OVH will publish it later
+    #[arg(long)]
+    no_zone_refresh: bool,
+    /// What to do when several records have the same subdomain and type: "fail", "update-all" or "update-first-delete-others"
+    #[arg(long, default_value_t = DuplicateRecordPolicy::default())]
+    duplicates: DuplicateRecordPolicy,
+    /// DNS zone of the record, when it isn't the registered domain, e.g. a delegated sub-zone
+    #[arg(long)]
+    zone: Option<String>,
+    /// Find the zone of the record among the DNS zones of the OVH account
+    #[arg(long, conflicts_with = "zone")]
+    discover_zone: bool,
+    /// What to do with the A record when the public IPv4 address can't be retrieved: "keep", "delete", or a fallback IPv4 address
+    #[arg(long, default_value_t = LostAddressPolicy::default())]
+    on_ip4_lost: LostAddressPolicy,
+    /// What to do with the AAAA record when the public IPv6 address can't be retrieved: "keep", "delete", or a fallback IPv6 address
+    #[arg(long, default_value_t = LostAddressPolicy::default())]
+    on_ip6_lost: LostAddressPolicy,
+    /// Service asked for the public IP addresses: "fastest", "ip-me", "ipify", "lafibre-info" or "ident-me"
+    #[arg(long, default_value_t = IpSource::default())]
+    ip_source: IpSource,
+    /// Number of retries of OVH API requests after a transient failure
+    #[arg(long, default_value_t = RetryPolicy::default().max_retries)]
+    retries: u32,
+    /// Look up the records, but only print the requests that would modify them
+    #[arg(long)]
+    dry_run: bool,
+    /// "table" prints the progress, "json" prints a report of each record at the end, for scripts and monitoring
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::default(), hide_possible_values = true)]
+    output: OutputFormat,
+    #[command(flatten)]
+    state_file_args: StateFileArgs,
+    #[command(flatten)]
+    log_args: LogArgs,
 }
 
-async fn manage_ip4_record_upgrade(
-    ip_retriever: &IpRetrieverFromFasterService,
-    ovh_dns_updater: &OvhDnsUpdater,
-    record_to_update: &str,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage OVH API credentials
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+    /// Check that the OVH credentials work and allow updating the given records
+    #[command(visible_alias = "whoami")]
+    Check(CheckArgs),
+    /// Show the changes needed to bring DNS zones to the state described in a TOML file
+    Plan(PlanArgs),
+    /// Create, update and delete records to bring DNS zones to the state described in a TOML file
+    Apply(ApplyArgs),
+    /// List the DNS zones of the OVH account
+    Zones(ZonesArgs),
+    /// List the records of a DNS zone
+    Records(RecordsArgs),
+    /// Delete the A and AAAA records (or the records of the given types) of a domain
+    Delete(DeleteArgs),
+    /// Keep the records of a config file up to date, checking the public IP addresses at a regular interval
+    Daemon(DaemonArgs),
+}
+
+/// Exit with a usage error if the fallback address of the lost address policy is of the wrong family
+fn check_fallback_address_family(
+    lost_address_policy: &LostAddressPolicy,
+    arg_name: &str,
+    is_expected_family: fn(&IpAddr) -> bool,
 ) {
-    let actual_ip4_future = ip_retriever.get_ip4();
-    let recorded_ip4_future = ovh_dns_updater.get_dns_ipv4(record_to_update);
-    let actual_ip4 = match actual_ip4_future.await {
-        Some(actual_ip4) => actual_ip4,
-        None => {
-            eprintln!("Cannot retrieve current IPv4");
-            std::process::exit(3);
+    if let LostAddressPolicy::Fallback(fallback_ip) = lost_address_policy {
+        if !is_expected_family(fallback_ip) {
+            Args::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "{} fallback address {} is of the wrong family",
+                        arg_name, fallback_ip
+                    ),
+                )
+                .exit();
         }
-    };
-    let recorded_ip4 = match recorded_ip4_future.await {
-        Ok(recorded_ip4) => recorded_ip4,
-        Err(e) => {
-            eprintln!("Cannot retrieve IPv4 record: {:?}", e);
-            std::process::exit(4);
-        }
-    };
-    println!("Actual IP4: {:?}", actual_ip4);
-    println!("Recorded IP4: {:?}", recorded_ip4);
-    if actual_ip4 != recorded_ip4 {
-        println!("Updating IP4 record...");
-        match ovh_dns_updater
-            .update_dns_ipv4(record_to_update, actual_ip4)
-            .await
-        {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("Cannot update IPv4 record: {:?}", e);
-                std::process::exit(5);
-            }
-        };
-        println!("IP4 record updated");
-    } else {
-        println!("IP4 record is up to date");
     }
 }
 
-async fn manage_ip6_record_upgrade(
-    ip_retriever: &IpRetrieverFromFasterService,
-    ovh_dns_updater: &OvhDnsUpdater,
-    record_to_update: &str,
-) {
-    let actual_ip6_future = ip_retriever.get_ip6();
-    let recorded_ip6_future = ovh_dns_updater.get_dns_ipv6(record_to_update);
-    let actual_ip6 = match actual_ip6_future.await {
-        Some(actual_ip6) => actual_ip6,
-        None => {
-            eprintln!("Cannot retrieve current IPv6");
-            std::process::exit(3);
-        }
-    };
-    let recorded_ip6 = match recorded_ip6_future.await {
-        Ok(recorded_ip6) => recorded_ip6,
-        Err(e) => {
-            eprintln!("Cannot retrieve IPv6 record: {:?}", e);
-            std::process::exit(4);
-        }
-    };
-    println!("Actual IP6: {:?}", actual_ip6);
-    println!("Recorded IP6: {:?}", recorded_ip6);
-    if actual_ip6 != recorded_ip6 {
-        println!("Updating IP6 record");
-        match ovh_dns_updater
-            .update_dns_ipv6(record_to_update, actual_ip6)
-            .await
-        {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("Cannot update IPv6 record: {:?}", e);
-                std::process::exit(5);
-            }
-        };
-        println!("IP6 record updated");
-    } else {
-        println!("IP6 record is up to date");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_arguments() {
+        Args::command().debug_assert();
     }
 }
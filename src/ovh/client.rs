@@ -1,11 +1,13 @@
 //! Inspired from https://github.com/MicroJoe/rust-ovh
 
-use reqwest::{header::HeaderMap, Response, StatusCode};
+use crate::ovh::ovh_api_error::{OvhApiError, OvhApiErrorBody};
+use crate::ovh::planned_request::PlannedRequest;
+use crate::ovh::retry_policy::RetryPolicy;
+use futures::lock::Mutex;
+use log::{debug, log_enabled, trace, warn, Level};
+use reqwest::{header::HeaderMap, Method, RequestBuilder, Response, StatusCode};
 use serde::Serialize;
-use std::{
-    convert::TryInto,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 static ENDPOINTS: phf::Map<&'static str, &'static str> = phf::phf_map! {
     "ovh-eu" => "https://eu.api.ovh.com/1.0",
@@ -17,14 +19,106 @@ static ENDPOINTS: phf::Map<&'static str, &'static str> = phf::phf_map! {
     "soyoustart-ca" => "https://ca.api.soyoustart.com/1.0",
 };
 
+/// Replaces the secrets in logs
+const REDACTED: &str = "***";
+
+/// Fields of the request and response bodies holding secrets, e.g. the consumer key of a new credential
+const SECRET_FIELDS: [&str; 2] = ["applicationSecret", "consumerKey"];
+
 // Private helpers
 
+/// Returns the API base URL of the given endpoint, which is either the name of a known
+/// endpoint (e.g. "ovh-eu") or a custom URL (e.g. "https://api.example.com/1.0").
+/// Plain HTTP is only accepted for local hosts, e.g. a mock server.
+pub(crate) fn resolve_endpoint(endpoint: &str) -> Option<String> {
+    if let Some(known_endpoint) = ENDPOINTS.get(endpoint) {
+        return Some(known_endpoint.to_string());
+    }
+    let endpoint_url = reqwest::Url::parse(endpoint).ok()?;
+    let host = endpoint_url.host_str()?;
+    let is_local_host = matches!(host, "localhost" | "127.0.0.1" | "[::1]");
+    match endpoint_url.scheme() {
+        "https" => (),
+        "http" if is_local_host => (),
+        _ => return None,
+    }
+    if endpoint_url.query().is_some() || endpoint_url.fragment().is_some() {
+        return None;
+    }
+    // Paths are appended to the endpoint, e.g. "/domain/zone"
+    Some(endpoint.trim_end_matches('/').to_string())
+}
+
 fn insert_sensitive_header(headers: &mut HeaderMap, header_name: &'static str, value: &str) {
     let mut header_value = reqwest::header::HeaderValue::from_str(value).unwrap();
     header_value.set_sensitive(true);
     headers.insert(header_name, header_value);
 }
 
+/// Turns a non-success response into the corresponding OvhApiError
+async fn check_response_status(resp: Response) -> Result<Response, OvhApiError> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let query_id = resp
+        .headers()
+        .get("X-Ovh-QueryID")
+        .and_then(|query_id| query_id.to_str().ok())
+        .map(String::from);
+    // The body may be missing or not be JSON, e.g. when an intermediate proxy answers
+    let body = resp.json::<OvhApiErrorBody>().await.unwrap_or_default();
+    Err(OvhApiError::Http {
+        status: status.as_u16(),
+        body,
+        query_id,
+    })
+}
+
+/// Replaces the values of the secret fields of a JSON body, other bodies are kept
+fn redact_json_secrets(body: &str) -> String {
+    fn redact_value(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (name, field_value) in fields.iter_mut() {
+                    if SECRET_FIELDS.contains(&name.as_str()) {
+                        *field_value = serde_json::Value::from(REDACTED);
+                    } else {
+                        redact_value(field_value);
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(redact_value),
+            _ => (),
+        }
+    }
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+/// Whether the request may succeed if sent again later
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by the server through the Retry-After header, in seconds
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let retry_after_seconds = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(retry_after_seconds))
+}
+
 fn now() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -32,26 +126,43 @@ fn now() -> u64 {
         .as_secs()
 }
 
+/// Number of seconds to add to the local time to get the server time.
+/// Signed, as the server clock can be either ahead or behind the local clock.
+fn compute_time_delta(server_time: u64, local_time: u64) -> i64 {
+    server_time as i64 - local_time as i64
+}
+
+struct CachedTimeDelta {
+    delta: i64,
+    computed_at: Instant,
+}
+
 // pub(crate)lic API
 
 pub(crate) struct OvhClient {
-    endpoint: &'static str,
+    endpoint: String,
     application_key: String,
     application_secret: String,
     consumer_key: String,
     client: reqwest::Client,
+    time_delta: Mutex<Option<CachedTimeDelta>>,
+    time_delta_refresh_interval: Option<Duration>,
+    retry_policy: RetryPolicy,
+    dry_run: bool,
+    /// Requests not sent because of the dry run, until they are taken
+    planned_requests: std::sync::Mutex<Vec<PlannedRequest>>,
 }
 
-#[allow(dead_code)]
 impl OvhClient {
     /// Creates a new client from scratch.
+    /// `endpoint` is either a known endpoint name or the base URL of the API.
     pub(crate) fn new(
         endpoint: &str,
         application_key: &str,
         application_secret: &str,
         consumer_key: &str,
     ) -> Option<OvhClient> {
-        let endpoint = ENDPOINTS.get(endpoint)?;
+        let endpoint = resolve_endpoint(endpoint)?;
         let application_key = application_key.into();
         let application_secret = application_secret.into();
         let consumer_key = consumer_key.into();
@@ -64,9 +175,40 @@ impl OvhClient {
             application_secret,
             consumer_key,
             client,
+            time_delta: Mutex::new(None),
+            time_delta_refresh_interval: None,
+            retry_policy: RetryPolicy::default(),
+            dry_run: false,
+            planned_requests: std::sync::Mutex::new(Vec::new()),
         })
     }
 
+    /// Sets how idempotent requests are retried after a transient failure.
+    pub(crate) fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Sets how often the time delta with the API server is computed again.
+    /// By default it is computed only once per client, or after a timestamp error.
+    pub(crate) fn set_time_delta_refresh_interval(&mut self, interval: Option<Duration>) {
+        self.time_delta_refresh_interval = interval;
+    }
+
+    /// In dry run, signed requests other than GET are kept as planned requests instead of being sent,
+    /// and succeed with an empty response.
+    pub(crate) fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Returns the requests not sent because of the dry run since the last call, in the order they were made
+    pub(crate) fn take_planned_requests(&self) -> Vec<PlannedRequest> {
+        std::mem::take(&mut *self.planned_requests.lock().unwrap())
+    }
+
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     fn signature(&self, url: &str, timestamp: &str, method: &str, body: &str) -> String {
         let values = [
             &self.application_secret,
@@ -84,17 +226,115 @@ impl OvhClient {
         format!("{}{}", &self.endpoint, path)
     }
 
+    /// Hides the credentials of the client and the secret fields of a body about to be logged
+    fn redact(&self, text: &str) -> String {
+        let mut redacted = redact_json_secrets(text);
+        for secret in [&self.application_secret, &self.consumer_key] {
+            if !secret.is_empty() {
+                redacted = redacted.replace(secret.as_str(), REDACTED);
+            }
+        }
+        redacted
+    }
+
+    /// Sends a request, logging its result and duration, and at trace level the bodies
+    async fn send_logged(
+        &self,
+        request: RequestBuilder,
+        method: &Method,
+        url: &str,
+        body: &str,
+    ) -> Result<Response, OvhApiError> {
+        if !body.is_empty() {
+            trace!("{} {} request body: {}", method, url, self.redact(body));
+        }
+        let start = Instant::now();
+        let resp = match request.send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                debug!(
+                    "{} {} failed after {} ms: {}",
+                    method,
+                    url,
+                    start.elapsed().as_millis(),
+                    e
+                );
+                return Err(e.into());
+            }
+        };
+        debug!(
+            "{} {} answered {} in {} ms",
+            method,
+            url,
+            resp.status(),
+            start.elapsed().as_millis()
+        );
+        if !log_enabled!(Level::Trace) {
+            return Ok(resp);
+        }
+        // The body can only be read once, the response is rebuilt for the caller
+        let mut traced_resp = http::Response::builder()
+            .status(resp.status())
+            .version(resp.version());
+        if let Some(headers) = traced_resp.headers_mut() {
+            *headers = resp.headers().clone();
+        }
+        let resp_body = resp.bytes().await?;
+        trace!(
+            "{} {} response body: {}",
+            method,
+            url,
+            self.redact(&String::from_utf8_lossy(&resp_body))
+        );
+        traced_resp
+            .body(resp_body)
+            .map(Response::from)
+            .map_err(|e| OvhApiError::InvalidResponse(e.to_string()))
+    }
+
     /// Retrieves the time delta between the local machine and the API server.
     ///
     /// This method will perform a request to the API server to get its
-    /// local time, and then subtract it from the local time of the machine.
+    /// local time, and then subtract the local time of the machine from it.
     /// The result is a time delta value, is seconds.
-    pub(crate) async fn time_delta(&self) -> Result<i64, Box<dyn std::error::Error>> {
-        let server_time: u64 = self.get_noauth("/auth/time").await?.text().await?.parse()?;
-        let delta = (now() - server_time).try_into()?;
+    pub(crate) async fn time_delta(&self) -> Result<i64, OvhApiError> {
+        let server_time: u64 = self
+            .get_noauth("/auth/time")
+            .await?
+            .text()
+            .await?
+            .trim()
+            .parse()
+            .map_err(|_| OvhApiError::InvalidResponse("invalid server time".to_string()))?;
+        Ok(compute_time_delta(server_time, now()))
+    }
+
+    /// Returns the cached time delta, retrieving it from the API server
+    /// if it is unknown or older than the refresh interval.
+    /// The lock is held while retrieving it, so that concurrent requests retrieve it only once.
+    async fn cached_time_delta(&self) -> Result<i64, OvhApiError> {
+        let mut cached_time_delta = self.time_delta.lock().await;
+        if let Some(cached) = cached_time_delta.as_ref() {
+            let expired = self
+                .time_delta_refresh_interval
+                .is_some_and(|interval| cached.computed_at.elapsed() >= interval);
+            if !expired {
+                return Ok(cached.delta);
+            }
+        }
+        let delta = self.time_delta().await?;
+        debug!("Time delta with the OVH API server: {} s", delta);
+        *cached_time_delta = Some(CachedTimeDelta {
+            delta,
+            computed_at: Instant::now(),
+        });
         Ok(delta)
     }
 
+    async fn invalidate_time_delta(&self) {
+        *self.time_delta.lock().await = None;
+    }
+
     fn default_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -109,15 +349,15 @@ impl OvhClient {
         url: &str,
         method: &str,
         body: &str,
-    ) -> Result<HeaderMap, Box<dyn std::error::Error>> {
+    ) -> Result<HeaderMap, OvhApiError> {
         let mut headers = self.default_headers();
         headers.insert(
             "Content-Type",
             reqwest::header::HeaderValue::from_static("application/json"),
         );
 
-        let time_delta = self.time_delta().await?;
-        let now: i64 = now().try_into()?;
+        let time_delta = self.cached_time_delta().await?;
+        let now: i64 = now() as i64;
         let timestamp = now + time_delta;
         let timestamp = timestamp.to_string();
 
@@ -130,22 +370,116 @@ impl OvhClient {
         Ok(headers)
     }
 
-    /// Performs a GET request.
-    pub(crate) async fn get(&self, path: &str) -> Result<Response, Box<dyn std::error::Error>> {
+    /// Sends a signed request.
+    ///
+    /// Idempotent requests are retried according to the retry policy after a
+    /// transient failure. If OVH rejects the timestamp, the time delta is
+    /// computed again and the request is sent once more.
+    async fn send_signed(
+        &self,
+        method: Method,
+        path: &str,
+        body: String,
+    ) -> Result<Response, OvhApiError> {
+        if self.dry_run && method != Method::GET {
+            let planned_request = PlannedRequest {
+                method: method.to_string(),
+                url: self.url(path),
+                body: (!body.is_empty()).then(|| self.redact(&body)),
+            };
+            debug!("Dry run, not sent: {}", planned_request);
+            self.planned_requests.lock().unwrap().push(planned_request);
+            return Ok(http::Response::new(String::new()).into());
+        }
+        let is_idempotent = matches!(method, Method::GET | Method::PUT | Method::DELETE);
+        let max_retries = if is_idempotent {
+            self.retry_policy.max_retries
+        } else {
+            0
+        };
+        let mut retry = 0;
+        let mut time_delta_resynced = false;
+        loop {
+            let send_result = self.send_signed_once(method.clone(), path, &body).await;
+            let transient_failure = match &send_result {
+                Err(OvhApiError::Transport(e)) => Some((e.clone(), None)),
+                Ok(resp) if is_transient_status(resp.status()) => {
+                    Some((format!("HTTP status {}", resp.status()), retry_after(resp)))
+                }
+                _ => None,
+            };
+            if let Some((failure_reason, requested_delay)) = transient_failure {
+                if retry < max_retries {
+                    match self.retry_policy.retry_delay(retry + 1, requested_delay) {
+                        Some(delay) => {
+                            retry += 1;
+                            warn!(
+                                "OVH API {} {} failed ({}), retry {}/{} in {:?}",
+                                method, path, failure_reason, retry, max_retries, delay
+                            );
+                            tokio::time::sleep(delay).await;
+                            continue;
+                        }
+                        None => warn!(
+                            "OVH API {} {} failed ({}), not retried as {:?} later is requested",
+                            method,
+                            path,
+                            failure_reason,
+                            requested_delay.unwrap_or_default()
+                        ),
+                    }
+                }
+            }
+            let result = match send_result {
+                Ok(resp) => check_response_status(resp).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Err(e) if e.is_timestamp_error() && !time_delta_resynced => {
+                    debug!("OVH rejected the timestamp, computing the time delta again");
+                    self.invalidate_time_delta().await;
+                    time_delta_resynced = true;
+                }
+                // The first attempt may have deleted the resource while its response was lost
+                Err(OvhApiError::Http { status: 404, .. })
+                    if method == Method::DELETE && retry > 0 =>
+                {
+                    debug!(
+                        "OVH API DELETE {} not found after a retry, it was already deleted",
+                        path
+                    );
+                    return Ok(http::Response::new(String::new()).into());
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends a signed request once, the response status is not checked.
+    async fn send_signed_once(
+        &self,
+        method: Method,
+        path: &str,
+        body: &str,
+    ) -> Result<Response, OvhApiError> {
         let url = self.url(path);
-        let headers = self.gen_headers(&url, "GET", "").await?;
+        let headers = self.gen_headers(&url, method.as_str(), body).await?;
+
+        let mut request = self.client.request(method.clone(), &url).headers(headers);
+        if !body.is_empty() {
+            request = request.body(body.to_string());
+        }
+        self.send_logged(request, &method, &url, body).await
+    }
 
-        let resp = self.client.get(url).headers(headers).send().await?;
-        Ok(resp)
+    /// Performs a GET request.
+    pub(crate) async fn get(&self, path: &str) -> Result<Response, OvhApiError> {
+        self.send_signed(Method::GET, path, String::new()).await
     }
 
     /// Performs a DELETE request.
-    pub(crate) async fn delete(&self, path: &str) -> Result<Response, Box<dyn std::error::Error>> {
-        let url = self.url(path);
-        let headers = self.gen_headers(&url, "DELETE", "").await?;
-
-        let resp = self.client.delete(url).headers(headers).send().await?;
-        Ok(resp)
+    pub(crate) async fn delete(&self, path: &str) -> Result<Response, OvhApiError> {
+        self.send_signed(Method::DELETE, path, String::new()).await
     }
 
     /// Performs a POST request.
@@ -153,22 +487,17 @@ impl OvhClient {
         &self,
         path: &str,
         data: &T,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
-        let url = self.url(path);
-
+    ) -> Result<Response, OvhApiError> {
         // Cannot call RequestBuilder.json directly because of body
         // signature requirement.
-        let body = serde_json::to_string(data)?;
-        let headers = self.gen_headers(&url, "POST", &body).await?;
+        let body =
+            serde_json::to_string(data).map_err(|e| OvhApiError::Serialization(e.to_string()))?;
+        self.send_signed(Method::POST, path, body).await
+    }
 
-        let resp = self
-            .client
-            .post(url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
-        Ok(resp)
+    /// Performs a POST request without body.
+    pub(crate) async fn post_without_body(&self, path: &str) -> Result<Response, OvhApiError> {
+        self.send_signed(Method::POST, path, String::new()).await
     }
 
     /// Performs a PUT request.
@@ -176,36 +505,195 @@ impl OvhClient {
         &self,
         path: &str,
         data: &T,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
-        let url = self.url(path);
-
+    ) -> Result<Response, OvhApiError> {
         // Cannot call RequestBuilder.json directly because of body
         // signature requirement.
-        let body = serde_json::to_string(data)?;
-        let headers = self.gen_headers(&url, "PUT", &body).await?;
-
-        let resp = self
-            .client
-            .put(url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
-        if resp.status() != StatusCode::OK {
-            return Err(Box::try_from("OVH API didn't returned status code 200").unwrap());
-        }
-        Ok(resp)
+        let body =
+            serde_json::to_string(data).map_err(|e| OvhApiError::Serialization(e.to_string()))?;
+        self.send_signed(Method::PUT, path, body).await
     }
 
     /// Performs a GET request without auth.
-    pub(crate) async fn get_noauth(
+    pub(crate) async fn get_noauth(&self, path: &str) -> Result<Response, OvhApiError> {
+        let url = self.url(path);
+        let headers = self.default_headers();
+
+        let request = self.client.get(&url).headers(headers);
+        let resp = self.send_logged(request, &Method::GET, &url, "").await?;
+        check_response_status(resp).await
+    }
+
+    /// Performs a POST request without auth.
+    pub(crate) async fn post_noauth<T: Serialize + ?Sized>(
         &self,
         path: &str,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
+        data: &T,
+    ) -> Result<Response, OvhApiError> {
         let url = self.url(path);
-        let headers = self.default_headers();
+        let mut headers = self.default_headers();
+        headers.insert(
+            "Content-Type",
+            reqwest::header::HeaderValue::from_static("application/json"),
+        );
+
+        let body =
+            serde_json::to_string(data).map_err(|e| OvhApiError::Serialization(e.to_string()))?;
+        let request = self.client.post(&url).headers(headers).body(body.clone());
+        let resp = self
+            .send_logged(request, &Method::POST, &url, &body)
+            .await?;
+        check_response_status(resp).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_endpoint() {
+        assert_eq!(
+            resolve_endpoint("ovh-eu"),
+            Some("https://eu.api.ovh.com/1.0".to_string())
+        );
+        assert_eq!(
+            resolve_endpoint("https://api.example.com/1.0/"),
+            Some("https://api.example.com/1.0".to_string())
+        );
+        assert_eq!(
+            resolve_endpoint("http://localhost:8080/1.0"),
+            Some("http://localhost:8080/1.0".to_string())
+        );
+        assert_eq!(resolve_endpoint("http://api.example.com/1.0"), None);
+        assert_eq!(resolve_endpoint("https://api.example.com/1.0?a=b"), None);
+        assert_eq!(resolve_endpoint("ovh-mars"), None);
+    }
+
+    #[test]
+    fn test_compute_time_delta() {
+        assert_eq!(compute_time_delta(1_000, 1_000), 0);
+        // Server clock ahead of the local clock
+        assert_eq!(compute_time_delta(1_010, 1_000), 10);
+        // Server clock behind the local clock
+        assert_eq!(compute_time_delta(1_000, 1_010), -10);
+    }
+
+    #[test]
+    fn test_redact() {
+        let client = OvhClient::new("ovh-eu", "ak", "app-secret", "consumer-key").unwrap();
+        assert_eq!(
+            client.redact("app-secret and consumer-key"),
+            "*** and ***".to_string()
+        );
+        assert_eq!(
+            client.redact(r#"{"state":"pendingValidation","consumerKey":"new-key"}"#),
+            r#"{"consumerKey":"***","state":"pendingValidation"}"#.to_string()
+        );
+        assert_eq!(
+            client.redact(r#"[{"target":"1.1.1.1"}]"#),
+            r#"[{"target":"1.1.1.1"}]"#.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_sends_no_mutation() {
+        // Nothing listens on this port, a request actually sent would fail
+        let mut client = OvhClient::new("http://localhost:9/1.0", "ak", "as", "ck").unwrap();
+        client.set_dry_run(true);
+        client.set_retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        });
+        assert!(client
+            .put(
+                "/domain/zone/example.com/record/1",
+                &serde_json::json!({"target": "1.1.1.1"})
+            )
+            .await
+            .is_ok());
+        assert!(client
+            .delete("/domain/zone/example.com/record/1")
+            .await
+            .is_ok());
+        assert!(client
+            .get("/domain/zone/example.com/record/1")
+            .await
+            .is_err());
+        assert_eq!(
+            client.take_planned_requests(),
+            vec![
+                PlannedRequest {
+                    method: "PUT".to_string(),
+                    url: "http://localhost:9/1.0/domain/zone/example.com/record/1".to_string(),
+                    body: Some(r#"{"target":"1.1.1.1"}"#.to_string()),
+                },
+                PlannedRequest {
+                    method: "DELETE".to_string(),
+                    url: "http://localhost:9/1.0/domain/zone/example.com/record/1".to_string(),
+                    body: None,
+                },
+            ]
+        );
+        assert!(client.take_planned_requests().is_empty());
+    }
+
+    /// Serves the given HTTP statuses in turn to the requests other than the server time one,
+    /// one connection per request.
+    fn serve_statuses(statuses: Vec<u16>) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/1.0", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut statuses = statuses.into_iter();
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let (status, body) = if String::from_utf8_lossy(&request).contains("/auth/time") {
+                    (200, now().to_string())
+                } else {
+                    let status = statuses.next().unwrap_or(500);
+                    (status, r#"{"message":"test"}"#.to_string())
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_retried_delete_not_found() {
+        let retry_policy = RetryPolicy {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        };
+
+        // The first attempt failed on the server side or its response was lost
+        let mut client = OvhClient::new(&serve_statuses(vec![503, 404]), "ak", "as", "ck").unwrap();
+        client.set_retry_policy(retry_policy.clone());
+        assert!(client
+            .delete("/domain/zone/example.com/record/1")
+            .await
+            .is_ok());
 
-        let resp = self.client.get(url).headers(headers).send().await?;
-        Ok(resp)
+        // Without a retry, the record didn't exist
+        let mut client = OvhClient::new(&serve_statuses(vec![404]), "ak", "as", "ck").unwrap();
+        client.set_retry_policy(retry_policy);
+        assert!(matches!(
+            client.delete("/domain/zone/example.com/record/1").await,
+            Err(OvhApiError::Http { status: 404, .. })
+        ));
     }
 }
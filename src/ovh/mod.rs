@@ -1 +1,4 @@
 pub(crate) mod client;
+pub mod ovh_api_error;
+pub mod planned_request;
+pub mod retry_policy;
@@ -0,0 +1,113 @@
+//! Errors returned by the OVH API client
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Error body returned by the OVH API along with a non-success HTTP status,
+/// e.g. `{"class":"Client::Forbidden","message":"This call has not been granted","errorCode":"..."}`
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct OvhApiErrorBody {
+    /// OVH error class, e.g. "Client::Forbidden"
+    pub class: Option<String>,
+    /// Human readable error message
+    pub message: Option<String>,
+    /// OVH error code, e.g. "INVALID_CREDENTIAL"
+    #[serde(rename = "errorCode")]
+    pub error_code: Option<String>,
+}
+
+impl OvhApiErrorBody {
+    fn describe(&self, query_id: &Option<String>) -> String {
+        let mut description = String::new();
+        if let Some(error_code) = &self.error_code {
+            description.push_str(&format!(" {}", error_code));
+        }
+        if let Some(message) = &self.message {
+            description.push_str(&format!(" \"{}\"", message));
+        }
+        if let Some(class) = &self.class {
+            description.push_str(&format!(" (class {})", class));
+        }
+        if let Some(query_id) = query_id {
+            description.push_str(&format!(" [query id {}]", query_id));
+        }
+        description
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OvhApiError {
+    #[error("Cannot reach OVH API: {0}")]
+    Transport(String),
+    #[error("OVH API returned HTTP status {status}:{}", .body.describe(.query_id))]
+    Http {
+        status: u16,
+        body: OvhApiErrorBody,
+        /// Value of the X-Ovh-QueryID header, useful when contacting OVH support
+        query_id: Option<String>,
+    },
+    #[error("Cannot serialize request body: {0}")]
+    Serialization(String),
+    #[error("Unexpected response from OVH API: {0}")]
+    InvalidResponse(String),
+}
+
+impl OvhApiError {
+    /// OVH error code of the response, e.g. "INVALID_CREDENTIAL"
+    pub fn error_code(&self) -> Option<&str> {
+        match self {
+            OvhApiError::Http { body, .. } => body.error_code.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether OVH rejected the request because of its timestamp,
+    /// meaning the time delta with the server must be computed again
+    pub(crate) fn is_timestamp_error(&self) -> bool {
+        match self {
+            OvhApiError::Http { body, .. } => {
+                body.error_code.as_deref() == Some("QUERY_TIME_OUT")
+                    || body
+                        .message
+                        .as_deref()
+                        .is_some_and(|message| message.contains("Query out of time"))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for OvhApiError {
+    fn from(e: reqwest::Error) -> Self {
+        OvhApiError::Transport(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_error_display() {
+        let body: OvhApiErrorBody = serde_json::from_str(
+            r#"{"class":"Client::Forbidden","message":"This call has not been granted","errorCode":"INVALID_CREDENTIAL","httpCode":"403 Forbidden"}"#,
+        )
+        .unwrap();
+        let error = OvhApiError::Http {
+            status: 403,
+            body,
+            query_id: Some("EU.ext-1.1234".to_string()),
+        };
+        assert_eq!(error.error_code(), Some("INVALID_CREDENTIAL"));
+        assert_eq!(
+            error.to_string(),
+            "OVH API returned HTTP status 403: INVALID_CREDENTIAL \"This call has not been granted\" (class Client::Forbidden) [query id EU.ext-1.1234]"
+        );
+        let error = OvhApiError::Http {
+            status: 502,
+            body: OvhApiErrorBody::default(),
+            query_id: None,
+        };
+        assert_eq!(error.to_string(), "OVH API returned HTTP status 502:");
+    }
+}
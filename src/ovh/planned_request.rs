@@ -0,0 +1,25 @@
+//! Requests of a dry run, kept instead of being sent to the OVH API
+
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Request that would have modified OVH data, the secrets of its body are hidden
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRequest {
+    /// HTTP method, e.g. "PUT"
+    pub method: String,
+    /// Full URL, e.g. "https://eu.api.ovh.com/1.0/domain/zone/example.com/record/1"
+    pub url: String,
+    /// JSON body, None if the request has none
+    pub body: Option<String>,
+}
+
+impl Display for PlannedRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+        if let Some(body) = &self.body {
+            write!(f, " {}", body)?;
+        }
+        Ok(())
+    }
+}
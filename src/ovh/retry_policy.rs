@@ -0,0 +1,106 @@
+//! Retry settings for requests sent to the OVH API
+
+use rand::Rng;
+use std::time::Duration;
+
+/// How idempotent requests (GET, PUT, DELETE) are retried after a transient failure:
+/// connection error, HTTP 429 or HTTP 5xx
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt, 0 disables retries
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each following retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn no_retry() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the given retry (starting at 1): exponential backoff,
+    /// of which a random half is removed so that clients don't retry all at once
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let exponential_backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        let half_backoff = exponential_backoff / 2;
+        half_backoff + rand::thread_rng().gen_range(Duration::ZERO..=half_backoff)
+    }
+
+    /// Delay before the given retry, the one requested by the server (Retry-After) if any
+    /// # Returns
+    /// None if the server requests a delay longer than `max_backoff`: the request isn't retried
+    /// rather than stalling the program, e.g. for a day
+    pub(crate) fn retry_delay(
+        &self,
+        retry: u32,
+        requested_delay: Option<Duration>,
+    ) -> Option<Duration> {
+        match requested_delay {
+            Some(requested_delay) if requested_delay > self.max_backoff => None,
+            Some(requested_delay) => Some(requested_delay),
+            None => Some(self.backoff(retry)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let retry_policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+        for _ in 0..100 {
+            let first_backoff = retry_policy.backoff(1);
+            assert!(first_backoff >= Duration::from_millis(500));
+            assert!(first_backoff <= Duration::from_secs(1));
+            let third_backoff = retry_policy.backoff(3);
+            assert!(third_backoff >= Duration::from_secs(2));
+            assert!(third_backoff <= Duration::from_secs(4));
+            let capped_backoff = retry_policy.backoff(10);
+            assert!(capped_backoff >= Duration::from_millis(2500));
+            assert!(capped_backoff <= Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retry_policy = RetryPolicy::default();
+        assert_eq!(
+            retry_policy.retry_delay(1, Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            retry_policy.retry_delay(1, Some(retry_policy.max_backoff)),
+            Some(retry_policy.max_backoff)
+        );
+        assert_eq!(
+            retry_policy.retry_delay(1, Some(Duration::from_secs(86400))),
+            None
+        );
+        assert!(retry_policy.retry_delay(1, None).unwrap() <= retry_policy.initial_backoff);
+    }
+}
@@ -0,0 +1,501 @@
+//! Typed model of the DNS records managed by OVH, whatever their type
+
+use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError;
+use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError::*;
+use crate::ovh_dns_updater::ovh_domain_response_obj::OvhDomainResponseObj;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// DNS record types (OVH `fieldType`) supported by OVH zones
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordType {
+    A,
+    Aaaa,
+    Caa,
+    Cname,
+    Dkim,
+    Dmarc,
+    Dname,
+    Https,
+    Loc,
+    Mx,
+    Naptr,
+    Ns,
+    Ptr,
+    Rp,
+    Spf,
+    Srv,
+    Sshfp,
+    Svcb,
+    Tlsa,
+    Txt,
+}
+
+impl DnsRecordType {
+    pub const ALL: [DnsRecordType; 20] = [
+        DnsRecordType::A,
+        DnsRecordType::Aaaa,
+        DnsRecordType::Caa,
+        DnsRecordType::Cname,
+        DnsRecordType::Dkim,
+        DnsRecordType::Dmarc,
+        DnsRecordType::Dname,
+        DnsRecordType::Https,
+        DnsRecordType::Loc,
+        DnsRecordType::Mx,
+        DnsRecordType::Naptr,
+        DnsRecordType::Ns,
+        DnsRecordType::Ptr,
+        DnsRecordType::Rp,
+        DnsRecordType::Spf,
+        DnsRecordType::Srv,
+        DnsRecordType::Sshfp,
+        DnsRecordType::Svcb,
+        DnsRecordType::Tlsa,
+        DnsRecordType::Txt,
+    ];
+
+    /// OVH `fieldType` of the record type, e.g. "AAAA"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DnsRecordType::A => "A",
+            DnsRecordType::Aaaa => "AAAA",
+            DnsRecordType::Caa => "CAA",
+            DnsRecordType::Cname => "CNAME",
+            DnsRecordType::Dkim => "DKIM",
+            DnsRecordType::Dmarc => "DMARC",
+            DnsRecordType::Dname => "DNAME",
+            DnsRecordType::Https => "HTTPS",
+            DnsRecordType::Loc => "LOC",
+            DnsRecordType::Mx => "MX",
+            DnsRecordType::Naptr => "NAPTR",
+            DnsRecordType::Ns => "NS",
+            DnsRecordType::Ptr => "PTR",
+            DnsRecordType::Rp => "RP",
+            DnsRecordType::Spf => "SPF",
+            DnsRecordType::Srv => "SRV",
+            DnsRecordType::Sshfp => "SSHFP",
+            DnsRecordType::Svcb => "SVCB",
+            DnsRecordType::Tlsa => "TLSA",
+            DnsRecordType::Txt => "TXT",
+        }
+    }
+
+    /// Check that the target is valid for this record type
+    /// # Arguments
+    /// * `target` - The record target as written in OVH zones, e.g. "10 mx1.example.com." for MX
+    /// # Returns
+    /// * `Ok(())` - If the target is valid
+    /// * `Err(String)` - The reason why the target is invalid
+    pub fn validate_target(&self, target: &str) -> Result<(), String> {
+        let fields: Vec<&str> = target.split_whitespace().collect();
+        match self {
+            DnsRecordType::A => target
+                .parse::<Ipv4Addr>()
+                .map(|_| ())
+                .map_err(|_| "expected an IPv4 address".to_string()),
+            DnsRecordType::Aaaa => target
+                .parse::<Ipv6Addr>()
+                .map(|_| ())
+                .map_err(|_| "expected an IPv6 address".to_string()),
+            DnsRecordType::Cname
+            | DnsRecordType::Dname
+            | DnsRecordType::Ns
+            | DnsRecordType::Ptr => validate_host_name(target),
+            DnsRecordType::Mx => match fields.as_slice() {
+                [priority, host] => {
+                    validate_number::<u16>(priority, "priority")?;
+                    validate_host_name(host)
+                }
+                _ => Err("expected \"<priority> <host>\"".to_string()),
+            },
+            DnsRecordType::Srv => match fields.as_slice() {
+                [priority, weight, port, host] => {
+                    validate_number::<u16>(priority, "priority")?;
+                    validate_number::<u16>(weight, "weight")?;
+                    validate_number::<u16>(port, "port")?;
+                    validate_host_name(host)
+                }
+                _ => Err("expected \"<priority> <weight> <port> <host>\"".to_string()),
+            },
+            DnsRecordType::Caa => match fields.as_slice() {
+                [flags, tag, _, ..] => {
+                    validate_number::<u8>(flags, "flags")?;
+                    if tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        Ok(())
+                    } else {
+                        Err(format!("invalid tag \"{}\"", tag))
+                    }
+                }
+                _ => Err("expected \"<flags> <tag> <value>\"".to_string()),
+            },
+            DnsRecordType::Sshfp => match fields.as_slice() {
+                [algorithm, fingerprint_type, fingerprint] => {
+                    validate_number::<u8>(algorithm, "algorithm")?;
+                    validate_number::<u8>(fingerprint_type, "fingerprint type")?;
+                    validate_hex(fingerprint, "fingerprint")
+                }
+                _ => Err("expected \"<algorithm> <type> <fingerprint>\"".to_string()),
+            },
+            DnsRecordType::Tlsa => match fields.as_slice() {
+                [usage, selector, matching_type, data] => {
+                    validate_number::<u8>(usage, "usage")?;
+                    validate_number::<u8>(selector, "selector")?;
+                    validate_number::<u8>(matching_type, "matching type")?;
+                    validate_hex(data, "certificate data")
+                }
+                _ => Err("expected \"<usage> <selector> <matching type> <data>\"".to_string()),
+            },
+            DnsRecordType::Spf => validate_contains(target, "v=spf1"),
+            DnsRecordType::Dmarc => validate_contains(target, "v=DMARC1"),
+            DnsRecordType::Dkim
+            | DnsRecordType::Https
+            | DnsRecordType::Loc
+            | DnsRecordType::Naptr
+            | DnsRecordType::Rp
+            | DnsRecordType::Svcb
+            | DnsRecordType::Txt => {
+                if fields.is_empty() {
+                    Err("expected a non-empty target".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+fn validate_number<T: FromStr>(field: &str, field_name: &str) -> Result<(), String> {
+    field
+        .parse::<T>()
+        .map(|_| ())
+        .map_err(|_| format!("invalid {} \"{}\"", field_name, field))
+}
+
+fn validate_hex(field: &str, field_name: &str) -> Result<(), String> {
+    if field.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(format!("invalid hexadecimal {} \"{}\"", field_name, field))
+    }
+}
+
+fn validate_contains(target: &str, marker: &str) -> Result<(), String> {
+    if target.to_lowercase().contains(&marker.to_lowercase()) {
+        Ok(())
+    } else {
+        Err(format!("expected a target containing \"{}\"", marker))
+    }
+}
+
+/// Host name, either relative to the zone ("www") or fully qualified ("www.example.com.")
+fn validate_host_name(host: &str) -> Result<(), String> {
+    let invalid_host_name = || Err(format!("invalid host name \"{}\"", host));
+    let host_without_root = host.strip_suffix('.').unwrap_or(host);
+    if host_without_root.is_empty() || host_without_root.len() > 253 {
+        return invalid_host_name();
+    }
+    for label in host_without_root.split('.') {
+        let valid_label = !label.is_empty()
+            && label.len() <= 63
+            && label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_label {
+            return invalid_host_name();
+        }
+    }
+    Ok(())
+}
+
+impl Display for DnsRecordType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DnsRecordType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|record_type| record_type.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown DNS record type \"{}\"", s))
+    }
+}
+
+/// A DNS record of an OVH zone, serialized with the field names of the OVH API
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsRecord {
+    /// OVH ID of the record, None if the record hasn't been created yet
+    pub id: Option<usize>,
+    /// The DNS zone of the record, e.g. "example.com"
+    pub zone: String,
+    /// The subdomain of the record in the zone, e.g. "www", empty for the zone apex
+    pub sub_domain: String,
+    #[serde(rename = "fieldType")]
+    pub record_type: DnsRecordType,
+    /// The record target, validated according to the record type
+    pub target: String,
+    /// TTL in seconds, 0 means the zone default TTL
+    pub ttl: u32,
+}
+
+impl DnsRecord {
+    /// Create a record that doesn't exist yet in the zone, checking that its target is valid
+    /// # Arguments
+    /// * `zone` - The DNS zone, e.g. "example.com"
+    /// * `sub_domain` - The subdomain in the zone, e.g. "www", empty for the zone apex
+    /// * `record_type` - The record type, e.g. `DnsRecordType::Mx`
+    /// * `target` - The record target, e.g. "10 mx1.example.com."
+    /// * `ttl` - TTL in seconds, 0 means the zone default TTL
+    pub fn new(
+        zone: &str,
+        sub_domain: &str,
+        record_type: DnsRecordType,
+        target: &str,
+        ttl: u32,
+    ) -> Result<Self, OvhDnsUpdaterError> {
+        let dns_record = Self {
+            id: None,
+            zone: zone.to_string(),
+            sub_domain: sub_domain.to_string(),
+            record_type,
+            target: target.to_string(),
+            ttl,
+        };
+        dns_record.validate()?;
+        Ok(dns_record)
+    }
+
+    /// Check that the target is valid for the record type
+    pub fn validate(&self) -> Result<(), OvhDnsUpdaterError> {
+        self.record_type
+            .validate_target(&self.target)
+            .map_err(|reason| InvalidRecordTarget {
+                record_type: self.record_type,
+                reason,
+            })
+    }
+
+    /// Full domain name of the record, e.g. "www.example.com"
+    pub fn full_domain(&self) -> String {
+        full_domain(&self.zone, &self.sub_domain)
+    }
+
+    /// Target in a canonical form, to compare targets written differently: addresses are parsed,
+    /// host names are lowercased and fully qualified, TXT values are unquoted
+    pub fn normalized_target(&self) -> String {
+        let target = self.target.trim();
+        match self.record_type {
+            DnsRecordType::A | DnsRecordType::Aaaa => target
+                .parse::<IpAddr>()
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|_| target.to_string()),
+            DnsRecordType::Cname
+            | DnsRecordType::Dname
+            | DnsRecordType::Ns
+            | DnsRecordType::Ptr => fully_qualified_host(target, &self.zone),
+            DnsRecordType::Mx | DnsRecordType::Srv => {
+                let mut fields: Vec<String> = target.split_whitespace().map(String::from).collect();
+                if let Some(host) = fields.last_mut() {
+                    *host = fully_qualified_host(host, &self.zone);
+                }
+                fields.join(" ")
+            }
+            DnsRecordType::Txt
+            | DnsRecordType::Spf
+            | DnsRecordType::Dkim
+            | DnsRecordType::Dmarc => unquote_txt(target),
+            _ => target.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Full domain name of a record of the zone, the zone itself for the apex
+pub(crate) fn full_domain(zone: &str, sub_domain: &str) -> String {
+    if sub_domain.is_empty() {
+        zone.to_string()
+    } else {
+        format!("{}.{}", sub_domain, zone)
+    }
+}
+
+/// Target and TTL of a record as shown to the user, e.g. "1.2.3.4 (TTL 60)"
+pub(crate) fn describe_record_content(target: &str, ttl: u32) -> String {
+    format!("{} (TTL {})", target, ttl)
+}
+
+/// Host name lowercased with its trailing dot, a single label is relative to the zone, e.g. "mx1"
+fn fully_qualified_host(host: &str, zone: &str) -> String {
+    let host = host.to_lowercase();
+    if host.ends_with('.') {
+        host
+    } else if host.contains('.') {
+        format!("{}.", host)
+    } else {
+        format!("{}.{}.", host, zone.to_lowercase())
+    }
+}
+
+/// Value of a TXT record without its quotes, a value split in several quoted strings is joined
+fn unquote_txt(value: &str) -> String {
+    if !value.starts_with('"') {
+        return value.to_string();
+    }
+    let mut unquoted = String::with_capacity(value.len());
+    let mut in_quotes = false;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => unquoted.extend(chars.next()),
+            c if in_quotes => unquoted.push(c),
+            // Spaces between the quoted strings
+            _ => (),
+        }
+    }
+    unquoted
+}
+
+impl TryFrom<OvhDomainResponseObj> for DnsRecord {
+    type Error = OvhDnsUpdaterError;
+
+    fn try_from(domain_response_obj: OvhDomainResponseObj) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Some(domain_response_obj.id),
+            zone: domain_response_obj.zone,
+            sub_domain: domain_response_obj.subDomain,
+            record_type: domain_response_obj
+                .fieldType
+                .parse()
+                .map_err(|_| IncorrectAPIResponseFormat)?,
+            target: domain_response_obj.target,
+            ttl: domain_response_obj.ttl,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_record_type_from_str() {
+        for record_type in DnsRecordType::ALL {
+            assert_eq!(record_type.as_str().parse(), Ok(record_type));
+        }
+        assert_eq!("aaaa".parse(), Ok(DnsRecordType::Aaaa));
+        assert!("SOA".parse::<DnsRecordType>().is_err());
+        assert_eq!(
+            serde_json::to_string(&DnsRecordType::Cname).unwrap(),
+            "\"CNAME\""
+        );
+    }
+
+    #[test]
+    fn test_validate_target() {
+        let valid_targets = [
+            (DnsRecordType::A, "1.2.3.4"),
+            (DnsRecordType::Aaaa, "2001:db8::1"),
+            (DnsRecordType::Cname, "home.example.com."),
+            (DnsRecordType::Ns, "dns1.ovh.net."),
+            (DnsRecordType::Mx, "10 mx1.example.com."),
+            (DnsRecordType::Srv, "0 5 5060 sip.example.com."),
+            (DnsRecordType::Caa, "0 issue \"letsencrypt.org\""),
+            (DnsRecordType::Sshfp, "4 2 0123456789abcdef"),
+            (DnsRecordType::Tlsa, "3 1 1 0123456789ABCDEF"),
+            (DnsRecordType::Spf, "\"v=spf1 include:mx.ovh.com ~all\""),
+            (DnsRecordType::Dmarc, "v=DMARC1; p=none"),
+            (DnsRecordType::Txt, "\"hello world\""),
+        ];
+        for (record_type, target) in valid_targets {
+            assert_eq!(record_type.validate_target(target), Ok(()), "{}", target);
+        }
+        let invalid_targets = [
+            (DnsRecordType::A, "2001:db8::1"),
+            (DnsRecordType::Aaaa, "1.2.3.4"),
+            (DnsRecordType::Cname, "home example.com"),
+            (DnsRecordType::Cname, "."),
+            (DnsRecordType::Mx, "mx1.example.com."),
+            (DnsRecordType::Mx, "70000 mx1.example.com."),
+            (DnsRecordType::Srv, "0 5 sip.example.com."),
+            (DnsRecordType::Caa, "0 issue"),
+            (DnsRecordType::Sshfp, "4 2 xyz"),
+            (DnsRecordType::Spf, "include:mx.ovh.com ~all"),
+            (DnsRecordType::Txt, " "),
+        ];
+        for (record_type, target) in invalid_targets {
+            assert!(record_type.validate_target(target).is_err(), "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_dns_record_new() {
+        let dns_record = DnsRecord::new("example.com", "www", DnsRecordType::A, "1.2.3.4", 60);
+        assert_eq!(dns_record.unwrap().full_domain(), "www.example.com");
+        let dns_record = DnsRecord::new("example.com", "", DnsRecordType::Mx, "mx1", 0);
+        assert!(matches!(dns_record, Err(InvalidRecordTarget { .. })));
+    }
+
+    #[test]
+    fn test_normalized_target() {
+        let normalized_target = |record_type, target: &str| {
+            DnsRecord::new("Example.com", "www", record_type, target, 0)
+                .unwrap()
+                .normalized_target()
+        };
+        assert_eq!(
+            normalized_target(DnsRecordType::Aaaa, "2001:0DB8:0:0::1"),
+            normalized_target(DnsRecordType::Aaaa, "2001:db8::1")
+        );
+        for cname in ["home", "Home.example.com", "home.example.com."] {
+            assert_eq!(
+                normalized_target(DnsRecordType::Cname, cname),
+                "home.example.com."
+            );
+        }
+        assert_eq!(
+            normalized_target(DnsRecordType::Mx, "10  MX1.Example.com"),
+            "10 mx1.example.com."
+        );
+        assert_eq!(
+            normalized_target(DnsRecordType::Srv, "0 5 5060 sip"),
+            "0 5 5060 sip.example.com."
+        );
+        assert_eq!(
+            normalized_target(DnsRecordType::Txt, "\"v=spf1 \" \"-all\""),
+            "v=spf1 -all"
+        );
+        assert_eq!(
+            normalized_target(DnsRecordType::Txt, "v=spf1 -all"),
+            "v=spf1 -all"
+        );
+        assert_eq!(
+            normalized_target(DnsRecordType::Txt, "\"say \\\"hi\\\"\""),
+            "say \"hi\""
+        );
+    }
+
+    #[test]
+    fn test_dns_record_json() {
+        let dns_record =
+            DnsRecord::new("example.com", "www", DnsRecordType::Cname, "home", 60).unwrap();
+        assert_eq!(
+            serde_json::to_value(&dns_record).unwrap(),
+            serde_json::json!({
+                "id": null,
+                "zone": "example.com",
+                "subDomain": "www",
+                "fieldType": "CNAME",
+                "target": "home",
+                "ttl": 60,
+            })
+        );
+    }
+}
@@ -1,12 +1,13 @@
+use crate::ovh_dns_updater::dns_record::DnsRecordType;
 use std::fmt::Display;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 
 pub(crate) trait GenericIpAddr {
     /// std::net::Ipv4Addr or std::net::Ipv6Addr
-    type IpAddrStruct: FromStr + Display;
-    /// "A" or "AAAA"
-    const DNS_ENTRY_FORMAT: &'static str;
+    type IpAddrStruct: FromStr + Display + PartialEq;
+    /// `DnsRecordType::A` or `DnsRecordType::Aaaa`
+    const RECORD_TYPE: DnsRecordType;
 }
 
 pub(crate) struct Ip4AddrStruct;
@@ -14,10 +15,10 @@ pub(crate) struct Ip6AddrStruct;
 
 impl GenericIpAddr for Ip4AddrStruct {
     type IpAddrStruct = Ipv4Addr;
-    const DNS_ENTRY_FORMAT: &'static str = "A";
+    const RECORD_TYPE: DnsRecordType = DnsRecordType::A;
 }
 
 impl GenericIpAddr for Ip6AddrStruct {
     type IpAddrStruct = Ipv6Addr;
-    const DNS_ENTRY_FORMAT: &'static str = "AAAA";
+    const RECORD_TYPE: DnsRecordType = DnsRecordType::Aaaa;
 }
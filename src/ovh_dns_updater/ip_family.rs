@@ -0,0 +1,39 @@
+//! Address family of a record pointing to the public IP address
+
+use crate::ovh_dns_updater::dns_record::DnsRecordType;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+
+/// IPv4 (A record) or IPv6 (AAAA record)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /// The type of the records holding addresses of this family
+    pub fn record_type(&self) -> DnsRecordType {
+        match self {
+            IpFamily::V4 => DnsRecordType::A,
+            IpFamily::V6 => DnsRecordType::Aaaa,
+        }
+    }
+
+    /// Whether the given address is of this family
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpFamily::V4 => ip.is_ipv4(),
+            IpFamily::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl Display for IpFamily {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IpFamily::V4 => "IPv4",
+            IpFamily::V6 => "IPv6",
+        })
+    }
+}
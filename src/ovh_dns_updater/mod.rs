@@ -1,23 +1,64 @@
-use crate::ovh::client::OvhClient;
+use crate::ovh::client::{resolve_endpoint, OvhClient};
+use crate::ovh::planned_request::PlannedRequest;
+use crate::ovh::retry_policy::RetryPolicy;
+use crate::ovh_dns_updater::dns_record::{DnsRecord, DnsRecordType};
 use crate::ovh_dns_updater::generic_ip_addr::{GenericIpAddr, Ip4AddrStruct, Ip6AddrStruct};
+use crate::ovh_dns_updater::ip_family::IpFamily;
+use crate::ovh_dns_updater::ovh_credential::{
+    AccessRule, CredentialCheck, CredentialRequest, OvhAccount, OvhCredential,
+};
 use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError;
 use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError::*;
 use crate::ovh_dns_updater::ovh_domain_response_obj::OvhDomainResponseObj;
+use crate::ovh_dns_updater::record_action::RecordAction;
+use crate::ovh_dns_updater::record_cache::RecordCache;
+use crate::ovh_dns_updater::record_options::{
+    DuplicateRecordPolicy, LostAddressPolicy, RecordOptions,
+};
+use crate::ovh_dns_updater::update_outcome::UpdateOutcome;
+use crate::ovh_dns_updater::zone_plan::{RecordChange, ZonePlan};
+use crate::ovh_dns_updater::zone_refresh::{ZoneRefreshPolicy, ZoneRefreshStatus};
+use crate::ovh_dns_updater::zone_state::ZoneState;
 use addr::parse_domain_name;
+use futures::{stream, StreamExt, TryStreamExt};
+use log::{debug, trace};
 use serde_json::json;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+pub mod dns_record;
 mod generic_ip_addr;
+pub mod ip_family;
+pub mod ovh_credential;
 pub mod ovh_dns_updater_error;
 mod ovh_domain_response_obj;
+pub mod record_action;
+mod record_cache;
+pub mod record_options;
+pub mod update_outcome;
+pub mod zone_plan;
+pub mod zone_refresh;
+pub mod zone_state;
 
 pub struct OvhDnsUpdater {
     ovh_client: OvhClient,
+    zone_refresh_policy: ZoneRefreshPolicy,
+    pending_zone_refreshes: Mutex<HashSet<String>>,
+    account_zones: Mutex<Option<Vec<String>>>,
+    record_cache: Mutex<RecordCache>,
 }
 
 impl OvhDnsUpdater {
-    const OVH_API_DOMAIN_ZONE_PREFIX: &'static str = "/domain/zone/";
+    pub(crate) const OVH_API_DOMAIN_ZONE_PREFIX: &'static str = "/domain/zone/";
+    /// Maximum number of OVH API requests sent at once when fetching many records
+    const MAX_CONCURRENT_REQUESTS: usize = 8;
 
+    /// Create a new DNS updater
+    /// # Arguments
+    /// * `ovh_endpoint` - Either a known endpoint name, e.g. "ovh-eu", or the base URL of the API, e.g. "https://eu.api.ovh.com/1.0"
+    /// * `ovh_application_key`, `ovh_application_secret`, `ovh_consumer_key` - The OVH API credentials
     pub fn new(
         ovh_endpoint: &str,
         ovh_application_key: &str,
@@ -31,98 +72,810 @@ impl OvhDnsUpdater {
                 ovh_application_secret,
                 ovh_consumer_key,
             )
-            .ok_or(OvhLoginError)?,
+            .ok_or_else(|| InvalidOvhEndpoint(ovh_endpoint.to_string()))?,
+            zone_refresh_policy: ZoneRefreshPolicy::default(),
+            pending_zone_refreshes: Mutex::new(HashSet::new()),
+            account_zones: Mutex::new(None),
+            record_cache: Mutex::new(RecordCache::new(RecordCache::DEFAULT_LIFETIME)),
         })
     }
 
-    async fn get_dns_record_id<IpVersion: GenericIpAddr>(
+    /// Check an endpoint without creating a DNS updater, e.g. when reading credentials from a file
+    /// # Arguments
+    /// * `ovh_endpoint` - Either a known endpoint name, e.g. "ovh-eu", or the base URL of the API
+    /// # Returns
+    /// * `Err(InvalidOvhEndpoint)` - If the DNS updater can't be created with this endpoint
+    pub fn check_endpoint(ovh_endpoint: &str) -> Result<(), OvhDnsUpdaterError> {
+        match resolve_endpoint(ovh_endpoint) {
+            Some(_) => Ok(()),
+            None => Err(InvalidOvhEndpoint(ovh_endpoint.to_string())),
+        }
+    }
+
+    /// Ask OVH for a new consumer key, granted the given access rules once validated by the user.
+    /// The application key and secret are used, the current consumer key is ignored.
+    /// # Arguments
+    /// * `access_rules` - The API calls the consumer key will be allowed to perform, see `AccessRule::for_zones()`
+    pub async fn request_credential(
         &self,
-        full_domain: &str,
-    ) -> Result<usize, OvhDnsUpdaterError> {
-        let (root_domain, sub_domain) = Self::separate_root_and_sub_domain(full_domain)?;
-        let ovh_domain_field_type = IpVersion::DNS_ENTRY_FORMAT;
-        let ovh_list_domains_id_api_path = format!(
-            "{}{}/record?fieldType={}&subDomain={}",
+        access_rules: &[AccessRule],
+    ) -> Result<CredentialRequest, OvhDnsUpdaterError> {
+        self.ovh_client
+            .post_noauth("/auth/credential", &json!({ "accessRules": access_rules }))
+            .await
+            .map_err(OvhCredentialError)?
+            .json::<CredentialRequest>()
+            .await
+            .map_err(|_| IncorrectAPIResponseFormat)
+    }
+
+    /// Retrieve the details of the credential (consumer key) used by this updater
+    pub async fn current_credential(&self) -> Result<OvhCredential, OvhDnsUpdaterError> {
+        self.ovh_client
+            .get("/auth/currentCredential")
+            .await
+            .map_err(OvhCredentialError)?
+            .json::<OvhCredential>()
+            .await
+            .map_err(|_| IncorrectAPIResponseFormat)
+    }
+
+    /// Retrieve the OVH account owning the credential used by this updater
+    pub async fn current_account(&self) -> Result<OvhAccount, OvhDnsUpdaterError> {
+        self.ovh_client
+            .get("/me")
+            .await
+            .map_err(OvhCredentialError)?
+            .json::<OvhAccount>()
+            .await
+            .map_err(|_| IncorrectAPIResponseFormat)
+    }
+
+    /// Check that the credential used by this updater works and allows keeping the records of the given zones up to date
+    /// # Arguments
+    /// * `zones` - The zones of the records that must be updatable, see `resolve_record_zone()`, e.g. ["example.com"]
+    /// # Returns
+    /// * `Ok(CredentialCheck)` - The credential details, its owner if allowed to call `GET /me`, and the missing rules
+    /// * `Err(OvhDnsUpdaterError)` - If the credential couldn't be retrieved, e.g. because it is invalid
+    pub async fn check_credential<'a>(
+        &self,
+        zones: impl IntoIterator<Item = &'a str>,
+    ) -> Result<CredentialCheck, OvhDnsUpdaterError> {
+        let credential = self.current_credential().await?;
+        let missing_rules = credential.missing_update_rules(zones);
+        // GET /me is not required by the updater, so it may not be granted
+        let account = self.current_account().await.ok();
+        Ok(CredentialCheck {
+            credential,
+            account,
+            missing_rules,
+        })
+    }
+
+    /// Set how requests to the OVH API are retried after a transient failure
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.ovh_client.set_retry_policy(retry_policy);
+    }
+
+    /// Set how often the time difference with the OVH API server is measured again.
+    /// By default it is measured once, then only when OVH rejects a request timestamp.
+    pub fn set_time_delta_refresh_interval(&mut self, interval: Option<Duration>) {
+        self.ovh_client.set_time_delta_refresh_interval(interval);
+    }
+
+    /// Set when DNS zones are refreshed after a record modification, default is `ZoneRefreshPolicy::Immediate`
+    pub fn set_zone_refresh_policy(&mut self, zone_refresh_policy: ZoneRefreshPolicy) {
+        self.zone_refresh_policy = zone_refresh_policy;
+    }
+
+    /// Set how long the records and record lists read from OVH are reused, default is 60 seconds.
+    /// The cache of a zone is dropped as soon as one of its records is modified by this updater, `Duration::ZERO` disables it.
+    pub fn set_record_cache_lifetime(&mut self, lifetime: Duration) {
+        self.record_cache.lock().unwrap().set_lifetime(lifetime);
+    }
+
+    /// In dry run, records are still read from OVH, but the requests creating, updating or deleting records
+    /// and refreshing zones are kept instead of being sent, see `take_planned_requests()`. Created records have no ID.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.ovh_client.set_dry_run(dry_run);
+    }
+
+    /// Returns the requests that the dry run didn't send since the last call
+    /// # Returns
+    /// The requests in the order they were made, with their body, empty if the updater isn't in dry run
+    pub fn take_planned_requests(&self) -> Vec<PlannedRequest> {
+        self.ovh_client.take_planned_requests()
+    }
+
+    /// Forget the cached records and zones, e.g. when they may have been modified outside of this updater
+    pub fn invalidate_record_cache(&self) {
+        self.record_cache.lock().unwrap().clear();
+        *self.account_zones.lock().unwrap() = None;
+    }
+
+    /// Ask OVH to publish the pending changes of the given DNS zone
+    /// # Arguments
+    /// * `zone` - The DNS zone to refresh, e.g. "example.com"
+    pub async fn refresh_zone(&self, zone: &str) -> Result<(), OvhDnsUpdaterError> {
+        let ovh_refresh_zone_api_path =
+            format!("{}{}/refresh", Self::OVH_API_DOMAIN_ZONE_PREFIX, zone);
+        self.ovh_client
+            .post_without_body(&ovh_refresh_zone_api_path)
+            .await
+            .map_err(OvhZoneRefreshError)?;
+        Ok(())
+    }
+
+    /// Refresh once each zone modified since the last call, when using `ZoneRefreshPolicy::Deferred`
+    /// # Returns
+    /// The refresh result of each zone
+    pub async fn refresh_pending_zones(&self) -> Vec<(String, Result<(), OvhDnsUpdaterError>)> {
+        let pending_zones: Vec<String> = self
+            .pending_zone_refreshes
+            .lock()
+            .unwrap()
+            .drain()
+            .collect();
+        let mut refresh_results = Vec::with_capacity(pending_zones.len());
+        for zone in pending_zones {
+            let refresh_result = self.refresh_zone(&zone).await;
+            refresh_results.push((zone, refresh_result));
+        }
+        refresh_results
+    }
+
+    /// Refresh the zone after a record modification, according to the refresh policy
+    async fn refresh_zone_after_change(&self, zone: &str) -> ZoneRefreshStatus {
+        match self.zone_refresh_policy {
+            ZoneRefreshPolicy::Immediate => match self.refresh_zone(zone).await {
+                Ok(()) => ZoneRefreshStatus::Refreshed,
+                Err(e) => ZoneRefreshStatus::Failed(e),
+            },
+            ZoneRefreshPolicy::Deferred => {
+                debug!("Refresh of zone {} deferred", zone);
+                self.pending_zone_refreshes
+                    .lock()
+                    .unwrap()
+                    .insert(zone.to_string());
+                ZoneRefreshStatus::Pending
+            }
+            ZoneRefreshPolicy::Skip => {
+                debug!("Refresh of zone {} skipped", zone);
+                ZoneRefreshStatus::Skipped
+            }
+        }
+    }
+
+    /// Returns the DNS zones of the OVH account that the credentials can reach
+    /// # Returns
+    /// * `Ok(Vec<String>)` - The zone names, sorted, e.g. ["example.com", "example.org"]
+    /// * `Err(OvhDnsUpdaterError)` - If the zones couldn't be retrieved, e.g. because `GET /domain/zone` isn't granted
+    pub async fn list_zones(&self) -> Result<Vec<String>, OvhDnsUpdaterError> {
+        let mut zones = self
+            .ovh_client
+            .get(Self::OVH_API_DOMAIN_ZONE_PREFIX.trim_end_matches('/'))
+            .await
+            .map_err(OvhDomainRetrievingError)?
+            .json::<Vec<String>>()
+            .await
+            .map_err(|_| IncorrectAPIResponseFormat)?;
+        zones.sort_unstable();
+        *self.account_zones.lock().unwrap() = Some(zones.clone());
+        Ok(zones)
+    }
+
+    fn record_api_path(zone: &str, record_id: usize) -> String {
+        format!(
+            "{}{}/record/{}",
             Self::OVH_API_DOMAIN_ZONE_PREFIX,
-            root_domain,
-            ovh_domain_field_type,
-            sub_domain
-        );
-        let domains_id_response = self
+            zone,
+            record_id
+        )
+    }
+
+    /// Returns the IDs of the matching DNS records of the zone, sorted, empty if there is none
+    async fn find_record_ids(
+        &self,
+        zone: &str,
+        record_type: Option<DnsRecordType>,
+        sub_domain: Option<&str>,
+    ) -> Result<Vec<usize>, OvhDnsUpdaterError> {
+        let mut filters = Vec::new();
+        if let Some(record_type) = record_type {
+            filters.push(format!("fieldType={}", record_type));
+        }
+        if let Some(sub_domain) = sub_domain {
+            filters.push(format!(
+                "subDomain={}",
+                Self::encode_query_value(sub_domain)
+            ));
+        }
+        let ovh_list_zone_records_id_api_path =
+            format!("{}{}/record", Self::OVH_API_DOMAIN_ZONE_PREFIX, zone);
+        let mut ovh_list_records_id_api_path = ovh_list_zone_records_id_api_path.clone();
+        if !filters.is_empty() {
+            ovh_list_records_id_api_path =
+                format!("{}?{}", ovh_list_records_id_api_path, filters.join("&"));
+        }
+        let cached_record_ids = {
+            let record_cache = self.record_cache.lock().unwrap();
+            record_cache
+                .record_ids(zone, &ovh_list_records_id_api_path)
+                .or_else(|| {
+                    // The whole zone may already be known, e.g. when several of its records are updated
+                    record_cache.matching_record_ids(
+                        zone,
+                        &ovh_list_zone_records_id_api_path,
+                        record_type,
+                        sub_domain,
+                    )
+                })
+        };
+        if let Some(cached_record_ids) = cached_record_ids {
+            trace!(
+                "Record IDs of {} found in cache",
+                ovh_list_records_id_api_path
+            );
+            return Ok(cached_record_ids);
+        }
+        let records_id_response = self
             .ovh_client
-            .get(&*ovh_list_domains_id_api_path)
+            .get(&ovh_list_records_id_api_path)
             .await
-            .map_err(|_| OvhDomainRetrievingError)?;
-        let domains_id_list = domains_id_response
+            .map_err(OvhDomainRetrievingError)?;
+        let mut records_id_list = records_id_response
             .json::<Vec<usize>>()
             .await
             .map_err(|_| IncorrectAPIResponseFormat)?;
-        if domains_id_list.len() != 1 {
+        records_id_list.sort_unstable();
+        self.record_cache.lock().unwrap().insert_record_ids(
+            zone,
+            &ovh_list_records_id_api_path,
+            &records_id_list,
+        );
+        Ok(records_id_list)
+    }
+
+    /// Returns the DNS record of the zone with the given ID
+    /// # Arguments
+    /// * `zone` - The DNS zone, e.g. "example.com"
+    /// * `record_id` - The OVH ID of the record
+    pub async fn get_record_by_id(
+        &self,
+        zone: &str,
+        record_id: usize,
+    ) -> Result<DnsRecord, OvhDnsUpdaterError> {
+        let cached_record = self.record_cache.lock().unwrap().record(zone, record_id);
+        if let Some(cached_record) = cached_record {
+            trace!("Record {} of zone {} found in cache", record_id, zone);
+            return Ok(cached_record);
+        }
+        let dns_record: DnsRecord = self
+            .ovh_client
+            .get(&Self::record_api_path(zone, record_id))
+            .await
+            .map_err(OvhDomainRetrievingError)?
+            .json::<OvhDomainResponseObj>()
+            .await
+            .map_err(|_| IncorrectAPIResponseFormat)?
+            .try_into()?;
+        self.record_cache
+            .lock()
+            .unwrap()
+            .insert_record(record_id, &dns_record);
+        Ok(dns_record)
+    }
+
+    /// Returns the DNS records of the zone, optionally filtered by type and subdomain
+    /// # Arguments
+    /// * `zone` - The DNS zone, e.g. "example.com"
+    /// * `record_type` - Only return records of this type, e.g. `Some(DnsRecordType::Txt)`
+    /// * `sub_domain` - Only return records of this subdomain, `Some("")` for the zone apex
+    /// # Returns
+    /// * `Ok(Vec<DnsRecord>)` - The matching records sorted by ID, empty if there is none
+    /// * `Err(OvhDnsUpdaterError)` - If the records couldn't be retrieved
+    pub async fn get_records(
+        &self,
+        zone: &str,
+        record_type: Option<DnsRecordType>,
+        sub_domain: Option<&str>,
+    ) -> Result<Vec<DnsRecord>, OvhDnsUpdaterError> {
+        let record_ids = self.find_record_ids(zone, record_type, sub_domain).await?;
+        self.get_records_by_ids(zone, record_ids).await
+    }
+
+    async fn get_records_by_ids(
+        &self,
+        zone: &str,
+        record_ids: Vec<usize>,
+    ) -> Result<Vec<DnsRecord>, OvhDnsUpdaterError> {
+        // A zone can hold hundreds of records, don't send all the requests at once
+        stream::iter(record_ids)
+            .map(|record_id| self.get_record_by_id(zone, record_id))
+            .buffered(Self::MAX_CONCURRENT_REQUESTS)
+            .try_collect()
+            .await
+    }
+
+    /// Create the given DNS record in its zone
+    /// # Arguments
+    /// * `dns_record` - The record to create, its ID is ignored
+    /// # Returns
+    /// * `Ok((DnsRecord, ZoneRefreshStatus))` - The created record with its ID, and whether the zone was refreshed
+    /// * `Err(OvhDnsUpdaterError)` - If the record target is invalid or the creation failed
+    pub async fn create_record(
+        &self,
+        dns_record: &DnsRecord,
+    ) -> Result<(DnsRecord, ZoneRefreshStatus), OvhDnsUpdaterError> {
+        let created_record = self.post_record(dns_record).await?;
+        Ok((
+            created_record,
+            self.refresh_zone_after_change(&dns_record.zone).await,
+        ))
+    }
+
+    /// Replace the subdomain, target and TTL of an existing DNS record, OVH doesn't allow changing its type
+    /// # Arguments
+    /// * `record_id` - The OVH ID of the record to update
+    /// * `dns_record` - The new content of the record, its ID is ignored
+    /// # Returns
+    /// * `Ok(ZoneRefreshStatus)` - Whether the zone was refreshed
+    /// * `Err(OvhDnsUpdaterError)` - If the record target is invalid or the update failed
+    pub async fn update_record_by_id(
+        &self,
+        record_id: usize,
+        dns_record: &DnsRecord,
+    ) -> Result<ZoneRefreshStatus, OvhDnsUpdaterError> {
+        dns_record.validate()?;
+        self.put_record(
+            &dns_record.zone,
+            record_id,
+            &json!({
+                "subDomain": dns_record.sub_domain,
+                "target": dns_record.target,
+                "ttl": dns_record.ttl,
+            }),
+        )
+        .await?;
+        Ok(self.refresh_zone_after_change(&dns_record.zone).await)
+    }
+
+    /// Delete the DNS record of the zone with the given ID
+    /// # Arguments
+    /// * `zone` - The DNS zone, e.g. "example.com"
+    /// * `record_id` - The OVH ID of the record to delete
+    /// # Returns
+    /// * `Ok(ZoneRefreshStatus)` - Whether the zone was refreshed
+    /// * `Err(OvhDnsUpdaterError)` - If the deletion failed
+    pub async fn delete_record_by_id(
+        &self,
+        zone: &str,
+        record_id: usize,
+    ) -> Result<ZoneRefreshStatus, OvhDnsUpdaterError> {
+        self.remove_record(zone, record_id).await?;
+        Ok(self.refresh_zone_after_change(zone).await)
+    }
+
+    /// Returns the records of the given domain and type
+    /// # Arguments
+    /// * `full_domain` - The full domain name, e.g. "sub.example.com"
+    /// * `record_type` - The record type, e.g. `DnsRecordType::A`
+    /// * `options` - The record options, `options.zone` and `options.discover_zone` tell how the zone is found
+    /// # Returns
+    /// * `Ok(Vec<DnsRecord>)` - The matching records sorted by ID, empty if there is none
+    /// * `Err(OvhDnsUpdaterError)` - If the records couldn't be retrieved
+    pub async fn find_records(
+        &self,
+        full_domain: &str,
+        record_type: DnsRecordType,
+        options: &RecordOptions,
+    ) -> Result<Vec<DnsRecord>, OvhDnsUpdaterError> {
+        let (zone, sub_domain) = self.resolve_record_zone(full_domain, options).await?;
+        self.get_records(&zone, Some(record_type), Some(&sub_domain))
+            .await
+    }
+
+    /// Delete all the records of the given domain and type
+    /// # Arguments
+    /// * `full_domain` - The full domain name, e.g. "sub.example.com"
+    /// * `record_type` - The record type, e.g. `DnsRecordType::Aaaa`
+    /// * `options` - The record options, `options.zone` and `options.discover_zone` tell how the zone is found
+    /// # Returns
+    /// * `Ok(ZoneRefreshStatus)` - Whether the zone was refreshed
+    /// * `Err(DomainZoneEntryDoesntExist)` - If there is no record to delete
+    /// * `Err(OvhDnsUpdaterError)` - If a deletion failed
+    pub async fn delete_record(
+        &self,
+        full_domain: &str,
+        record_type: DnsRecordType,
+        options: &RecordOptions,
+    ) -> Result<ZoneRefreshStatus, OvhDnsUpdaterError> {
+        let (zone, sub_domain) = self.resolve_record_zone(full_domain, options).await?;
+        let record_ids = self
+            .find_record_ids(&zone, Some(record_type), Some(&sub_domain))
+            .await?;
+        if record_ids.is_empty() {
             return Err(DomainZoneEntryDoesntExist);
         }
-        Ok(domains_id_list[0])
+        for record_id in record_ids {
+            self.remove_record(&zone, record_id).await?;
+        }
+        Ok(self.refresh_zone_after_change(&zone).await)
     }
 
-    async fn get_dns_ip_4_or_6<IpVersion: GenericIpAddr>(
+    /// Bring the A or AAAA records of the given domain to the public address, in a single call:
+    /// creates, updates or deletes the records according to the options, then refreshes the zone if something changed
+    /// # Arguments
+    /// * `full_domain` - The full domain name, e.g. "sub.example.com"
+    /// * `ip_family` - `IpFamily::V4` for the A records, `IpFamily::V6` for the AAAA records
+    /// * `public_ip` - The current public address, None if it couldn't be retrieved, `options.lost_address` then tells what to do
+    /// * `options` - The record options
+    /// # Returns
+    /// * `Ok(Vec<UpdateOutcome>)` - The outcome of each record sorted by ID, empty if there is no record and none had to be created
+    /// * `Err(DomainZoneEntryDoesntExist)` - If there is no record and `options.create_if_missing` isn't set
+    /// * `Err(AmbiguousRecord)` - If there are several records and duplicates aren't accepted
+    /// * `Err(InvalidRecordTarget)` - If the address to set isn't of the given family
+    /// * `Err(OvhDnsUpdaterError)` - If the records couldn't be retrieved or a change failed, the changes already made are still published
+    pub async fn sync_record(
         &self,
         full_domain: &str,
-    ) -> Result<IpVersion::IpAddrStruct, OvhDnsUpdaterError> {
-        let domain_id = self.get_dns_record_id::<IpVersion>(full_domain).await?;
-        let (root_domain, _) = Self::separate_root_and_sub_domain(full_domain)?;
-        let ovh_get_domain_api_path = format!(
-            "{}{}/record/{}",
+        ip_family: IpFamily,
+        public_ip: Option<IpAddr>,
+        options: &RecordOptions,
+    ) -> Result<Vec<UpdateOutcome>, OvhDnsUpdaterError> {
+        let sync_start = Instant::now();
+        let record_type = ip_family.record_type();
+        let target_ip = match (public_ip, options.lost_address) {
+            (Some(public_ip), _) => Some(public_ip),
+            (None, LostAddressPolicy::Fallback(fallback_ip)) => Some(fallback_ip),
+            (None, _) => None,
+        };
+        if let Some(target_ip) = target_ip.filter(|target_ip| !ip_family.contains(target_ip)) {
+            return Err(InvalidRecordTarget {
+                record_type,
+                reason: format!("{} isn't an {} address", target_ip, ip_family),
+            });
+        }
+        let delete_records =
+            public_ip.is_none() && options.lost_address == LostAddressPolicy::Delete;
+
+        let (zone, sub_domain) = self.resolve_record_zone(full_domain, options).await?;
+        let record_ids = self
+            .find_record_ids(&zone, Some(record_type), Some(&sub_domain))
+            .await?;
+        debug!(
+            "{} {} records in zone {}: {:?}",
+            full_domain, record_type, zone, record_ids
+        );
+        if record_ids.len() > 1
+            && options.duplicates == DuplicateRecordPolicy::Fail
+            && !delete_records
+        {
+            return Err(AmbiguousRecord { ids: record_ids });
+        }
+        let mut current_records = self.get_records_by_ids(&zone, record_ids).await?;
+
+        let mut record_changes = Vec::new();
+        let mut unchanged_records = Vec::new();
+        match target_ip {
+            None if delete_records => {
+                record_changes.extend(current_records.into_iter().map(RecordChange::Delete))
+            }
+            None => unchanged_records = current_records,
+            Some(target_ip) if current_records.is_empty() => {
+                if !options.create_if_missing {
+                    return Err(DomainZoneEntryDoesntExist);
+                }
+                record_changes.push(RecordChange::Create(DnsRecord::new(
+                    &zone,
+                    &sub_domain,
+                    record_type,
+                    &target_ip.to_string(),
+                    options.ttl.creation_ttl(),
+                )?));
+            }
+            Some(target_ip) => {
+                let duplicate_records =
+                    if options.duplicates == DuplicateRecordPolicy::UpdateFirstDeleteOthers {
+                        current_records.split_off(1)
+                    } else {
+                        Vec::new()
+                    };
+                for current_record in current_records {
+                    let ttl = options.ttl.update_ttl().unwrap_or(current_record.ttl);
+                    // Compare addresses rather than strings, OVH may format IPv6 addresses differently
+                    if current_record.target.parse::<IpAddr>() == Ok(target_ip)
+                        && current_record.ttl == ttl
+                    {
+                        unchanged_records.push(current_record);
+                        continue;
+                    }
+                    let desired_record = DnsRecord {
+                        target: target_ip.to_string(),
+                        ttl,
+                        ..current_record.clone()
+                    };
+                    record_changes.push(RecordChange::Update {
+                        current: current_record,
+                        desired: desired_record,
+                    });
+                }
+                record_changes.extend(duplicate_records.into_iter().map(RecordChange::Delete));
+            }
+        }
+
+        let mut update_outcomes = Vec::with_capacity(record_changes.len());
+        for record_change in &record_changes {
+            let changed_record = match self.apply_record_change(record_change).await {
+                Ok(changed_record) => changed_record,
+                Err(e) => {
+                    // Still publish the changes already applied
+                    if !update_outcomes.is_empty() {
+                        self.refresh_zone_after_change(&zone).await;
+                    }
+                    return Err(e);
+                }
+            };
+            update_outcomes.push(match record_change {
+                RecordChange::Create(_) => {
+                    UpdateOutcome::new(RecordAction::Created, None, Some(&changed_record))
+                }
+                RecordChange::Update { current, .. } => {
+                    UpdateOutcome::new(RecordAction::Updated, Some(current), Some(&changed_record))
+                }
+                RecordChange::Delete(_) => {
+                    UpdateOutcome::new(RecordAction::Deleted, Some(&changed_record), None)
+                }
+            });
+        }
+        if !record_changes.is_empty() {
+            let zone_refresh = self.refresh_zone_after_change(&zone).await;
+            for update_outcome in &mut update_outcomes {
+                update_outcome.zone_refresh = zone_refresh.clone();
+            }
+        }
+        update_outcomes.extend(unchanged_records.iter().map(|unchanged_record| {
+            UpdateOutcome::new(
+                RecordAction::Unchanged,
+                Some(unchanged_record),
+                Some(unchanged_record),
+            )
+        }));
+        update_outcomes.sort_by_key(|update_outcome| update_outcome.record_id);
+        let sync_duration = sync_start.elapsed();
+        for update_outcome in &mut update_outcomes {
+            update_outcome.duration = sync_duration;
+        }
+        Ok(update_outcomes)
+    }
+
+    /// Compare the live records of a zone with its desired state
+    /// # Arguments
+    /// * `zone_state` - The desired records of the zone
+    /// * `public_ipv4`, `public_ipv6` - The current public addresses, only needed if a record uses them
+    /// # Returns
+    /// * `Ok(ZonePlan)` - The changes needed to bring the zone to its desired state
+    /// * `Err(OvhDnsUpdaterError)` - If the zone state is invalid or the live records couldn't be retrieved
+    pub async fn plan_zone(
+        &self,
+        zone_state: &ZoneState,
+        public_ipv4: Option<Ipv4Addr>,
+        public_ipv6: Option<Ipv6Addr>,
+    ) -> Result<ZonePlan, OvhDnsUpdaterError> {
+        let desired_records = zone_state.desired_records(public_ipv4, public_ipv6)?;
+        let live_records = self.get_records(&zone_state.name, None, None).await?;
+        Ok(ZonePlan::compute(
+            &zone_state.name,
+            live_records,
+            desired_records,
+            zone_state.prune,
+        ))
+    }
+
+    /// Apply the changes of a zone plan, deletions first, then refresh the zone once
+    /// # Arguments
+    /// * `zone_plan` - The changes to apply, see `plan_zone()`
+    /// # Returns
+    /// * `Ok(ZoneRefreshStatus)` - Whether the zone was refreshed, `Skipped` if there was no change
+    /// * `Err(OvhDnsUpdaterError)` - The first change that failed, the following ones aren't applied
+    pub async fn apply_zone_plan(
+        &self,
+        zone_plan: &ZonePlan,
+    ) -> Result<ZoneRefreshStatus, OvhDnsUpdaterError> {
+        if zone_plan.is_empty() {
+            return Ok(ZoneRefreshStatus::Skipped);
+        }
+        let mut record_changes: Vec<&RecordChange> = zone_plan.changes.iter().collect();
+        record_changes.sort_by_key(|record_change| record_change.apply_order());
+        for (applied_changes, record_change) in record_changes.into_iter().enumerate() {
+            if let Err(e) = self.apply_record_change(record_change).await {
+                // Still publish the changes already applied
+                if applied_changes > 0 {
+                    self.refresh_zone_after_change(&zone_plan.zone).await;
+                }
+                return Err(e);
+            }
+        }
+        Ok(self.refresh_zone_after_change(&zone_plan.zone).await)
+    }
+
+    /// Apply a single record change without refreshing the zone
+    /// # Returns
+    /// * `Ok(DnsRecord)` - The created record with its ID, the updated record, or the deleted record
+    /// * `Err(OvhDnsUpdaterError)` - If the change failed
+    async fn apply_record_change(
+        &self,
+        record_change: &RecordChange,
+    ) -> Result<DnsRecord, OvhDnsUpdaterError> {
+        match record_change {
+            RecordChange::Create(desired) => {
+                debug!(
+                    "Creating {} record {} in zone {} with target {}",
+                    desired.record_type, desired.sub_domain, desired.zone, desired.target
+                );
+                self.post_record(desired).await
+            }
+            RecordChange::Update { current, desired } => {
+                let record_id = current.id.ok_or(DomainZoneEntryDoesntExist)?;
+                debug!(
+                    "Updating record {} of zone {}: target {} -> {}, TTL {} -> {}",
+                    record_id,
+                    current.zone,
+                    current.target,
+                    desired.target,
+                    current.ttl,
+                    desired.ttl
+                );
+                desired.validate()?;
+                self.put_record(
+                    &current.zone,
+                    record_id,
+                    &json!({
+                        "subDomain": desired.sub_domain,
+                        "target": desired.target,
+                        "ttl": desired.ttl,
+                    }),
+                )
+                .await?;
+                Ok(desired.clone())
+            }
+            RecordChange::Delete(current) => {
+                let record_id = current.id.ok_or(DomainZoneEntryDoesntExist)?;
+                debug!("Deleting record {} of zone {}", record_id, current.zone);
+                self.remove_record(&current.zone, record_id).await?;
+                Ok(current.clone())
+            }
+        }
+    }
+
+    /// Create the record without refreshing the zone
+    async fn post_record(&self, dns_record: &DnsRecord) -> Result<DnsRecord, OvhDnsUpdaterError> {
+        dns_record.validate()?;
+        let ovh_create_record_api_path = format!(
+            "{}{}/record",
             Self::OVH_API_DOMAIN_ZONE_PREFIX,
-            root_domain,
-            domain_id
+            dns_record.zone
         );
-        let domain_response = self
+        self.record_cache
+            .lock()
+            .unwrap()
+            .invalidate_zone(&dns_record.zone);
+        let response = self
             .ovh_client
-            .get(&*ovh_get_domain_api_path)
+            .post(
+                &ovh_create_record_api_path,
+                &json!({
+                    "fieldType": dns_record.record_type,
+                    "subDomain": dns_record.sub_domain,
+                    "target": dns_record.target,
+                    "ttl": dns_record.ttl,
+                }),
+            )
             .await
-            .map_err(|_| OvhDomainRetrievingError)?;
-        let domain_response_obj = domain_response
+            .map_err(OvhDomainCreatingError)?;
+        // Nothing has been created, so there is no ID
+        if self.ovh_client.is_dry_run() {
+            return Ok(dns_record.clone());
+        }
+        response
             .json::<OvhDomainResponseObj>()
             .await
-            .map_err(|_| IncorrectAPIResponseFormat)?;
-        Ok(domain_response_obj
-            .target
-            .parse::<IpVersion::IpAddrStruct>()
-            .map_err(|_| IncorrectAPIResponseFormat)?)
+            .map_err(|_| IncorrectAPIResponseFormat)?
+            .try_into()
     }
 
-    async fn update_dns_ip_4_or_6<IpVersion: GenericIpAddr>(
+    /// Update the record without refreshing the zone
+    async fn put_record(
         &self,
-        full_domain: &str,
-        ip: IpVersion::IpAddrStruct,
+        zone: &str,
+        record_id: usize,
+        update_body: &serde_json::Value,
     ) -> Result<(), OvhDnsUpdaterError> {
-        let domain_id = self.get_dns_record_id::<IpVersion>(full_domain).await?;
-        let (root_domain, sub_domain) = Self::separate_root_and_sub_domain(full_domain)?;
-        let ovh_update_domain_api_path = format!(
-            "{}{}/record/{}",
-            Self::OVH_API_DOMAIN_ZONE_PREFIX,
-            root_domain,
-            domain_id
-        );
+        self.record_cache.lock().unwrap().invalidate_zone(zone);
         self.ovh_client
-            .put(
-                &*ovh_update_domain_api_path,
-                &json!({
-                    "subDomain": sub_domain,
-                    "target": ip.to_string(),
-                    "ttl": 0,
-                }),
-            )
+            .put(&Self::record_api_path(zone, record_id), update_body)
+            .await
+            .map_err(OvhDomainUpdatingError)?;
+        Ok(())
+    }
+
+    /// Delete the record without refreshing the zone
+    async fn remove_record(&self, zone: &str, record_id: usize) -> Result<(), OvhDnsUpdaterError> {
+        self.record_cache.lock().unwrap().invalidate_zone(zone);
+        self.ovh_client
+            .delete(&Self::record_api_path(zone, record_id))
             .await
-            .map_err(|_| OvhDomainUpdatingError)?;
+            .map_err(OvhDomainDeletingError)?;
         Ok(())
     }
 
+    /// Returns the zones of the OVH account, retrieved once then cached
+    async fn account_zones(&self) -> Result<Vec<String>, OvhDnsUpdaterError> {
+        let cached_account_zones = self.account_zones.lock().unwrap().clone();
+        match cached_account_zones {
+            Some(account_zones) => Ok(account_zones),
+            None => self.list_zones().await,
+        }
+    }
+
+    /// Returns the zone and the subdomain of the record: the zone given in the options,
+    /// the longest matching zone of the account if discovery is enabled, or else the guess of the public suffix list
+    /// # Arguments
+    /// * `full_domain` - The record, e.g. "nas.home.example.co.uk"
+    /// * `options` - The record options, only `zone` and `discover_zone` are used
+    /// # Returns
+    /// * `Ok((zone, sub_domain))` - e.g. ("home.example.co.uk", "nas")
+    /// * `Err(OvhDnsUpdaterError)` - If the record isn't in the zone, or the zones of the account couldn't be retrieved
+    pub async fn resolve_record_zone(
+        &self,
+        full_domain: &str,
+        options: &RecordOptions,
+    ) -> Result<(String, String), OvhDnsUpdaterError> {
+        let (zone, sub_domain) = match &options.zone {
+            Some(zone) => Self::separate_zone_and_sub_domain(full_domain, zone)?,
+            None if options.discover_zone => {
+                let account_zones = self.account_zones().await?;
+                let (zone, sub_domain) = Self::find_zone_of_record(full_domain, &account_zones)?;
+                debug!("{} found in zone {} of the account", full_domain, zone);
+                return Ok((zone.to_string(), sub_domain.to_string()));
+            }
+            None => Self::separate_root_and_sub_domain(full_domain)?,
+        };
+        Ok((zone.to_string(), sub_domain.to_string()))
+    }
+
+    async fn get_dns_ip_4_or_6<IpVersion: GenericIpAddr>(
+        &self,
+        full_domain: &str,
+    ) -> Result<IpVersion::IpAddrStruct, OvhDnsUpdaterError> {
+        let dns_records = self
+            .find_records(
+                full_domain,
+                IpVersion::RECORD_TYPE,
+                &RecordOptions::default(),
+            )
+            .await?;
+        match dns_records.as_slice() {
+            [] => Err(DomainZoneEntryDoesntExist),
+            [dns_record] => dns_record
+                .target
+                .parse::<IpVersion::IpAddrStruct>()
+                .map_err(|_| IncorrectAPIResponseFormat),
+            _ => Err(AmbiguousRecord {
+                ids: dns_records
+                    .iter()
+                    .filter_map(|dns_record| dns_record.id)
+                    .collect(),
+            }),
+        }
+    }
+
     pub async fn get_dns_ipv4(&self, full_domain: &str) -> Result<Ipv4Addr, OvhDnsUpdaterError> {
-        Ok(self.get_dns_ip_4_or_6::<Ip4AddrStruct>(full_domain).await?)
+        self.get_dns_ip_4_or_6::<Ip4AddrStruct>(full_domain).await
     }
 
     pub async fn get_dns_ipv6(&self, full_domain: &str) -> Result<Ipv6Addr, OvhDnsUpdaterError> {
-        Ok(self.get_dns_ip_4_or_6::<Ip6AddrStruct>(full_domain).await?)
+        self.get_dns_ip_4_or_6::<Ip6AddrStruct>(full_domain).await
     }
 
     /// Update the DNS record of the given domain with the given IPv4 address
@@ -130,14 +883,14 @@ impl OvhDnsUpdater {
     /// * `full_domain` - The full domain name to update, e.g. "sub.example.com" will update the "sub" subdomain of "example.com"
     /// * `ipv4` - The IPv4 address to set
     /// # Returns
-    /// * `Ok(())` - If the update was successful
+    /// * `Ok(Vec<UpdateOutcome>)` - If the update was successful, see `sync_record()`
     /// * `Err(OvhDnsUpdaterError)` - If the update failed
     pub async fn update_dns_ipv4(
         &self,
         full_domain: &str,
         ipv4: Ipv4Addr,
-    ) -> Result<(), OvhDnsUpdaterError> {
-        self.update_dns_ip_4_or_6::<Ip4AddrStruct>(full_domain, ipv4)
+    ) -> Result<Vec<UpdateOutcome>, OvhDnsUpdaterError> {
+        self.update_dns_ipv4_with_options(full_domain, ipv4, &RecordOptions::default())
             .await
     }
 
@@ -146,14 +899,50 @@ impl OvhDnsUpdater {
     /// * `full_domain` - The full domain name to update, e.g. "sub.example.com" will update the "sub" subdomain of "example.com"
     /// * `ipv6` - The IPv6 address to set
     /// # Returns
-    /// * `Ok(())` - If the update was successful
+    /// * `Ok(Vec<UpdateOutcome>)` - If the update was successful, see `sync_record()`
     /// * `Err(OvhDnsUpdaterError)` - If the update failed
     pub async fn update_dns_ipv6(
         &self,
         full_domain: &str,
         ipv6: Ipv6Addr,
-    ) -> Result<(), OvhDnsUpdaterError> {
-        self.update_dns_ip_4_or_6::<Ip6AddrStruct>(full_domain, ipv6)
+    ) -> Result<Vec<UpdateOutcome>, OvhDnsUpdaterError> {
+        self.update_dns_ipv6_with_options(full_domain, ipv6, &RecordOptions::default())
+            .await
+    }
+
+    /// Update the DNS record of the given domain with the given IPv4 address, using the given record options
+    /// # Arguments
+    /// * `full_domain` - The full domain name to update, e.g. "sub.example.com" will update the "sub" subdomain of "example.com"
+    /// * `ipv4` - The IPv4 address to set
+    /// * `options` - The record options, e.g. whether the record should be created if it doesn't exist
+    /// # Returns
+    /// * `Ok(Vec<UpdateOutcome>)` - What has been done on each A record, see `sync_record()`
+    /// * `Err(OvhDnsUpdaterError)` - If the update failed
+    pub async fn update_dns_ipv4_with_options(
+        &self,
+        full_domain: &str,
+        ipv4: Ipv4Addr,
+        options: &RecordOptions,
+    ) -> Result<Vec<UpdateOutcome>, OvhDnsUpdaterError> {
+        self.sync_record(full_domain, IpFamily::V4, Some(IpAddr::V4(ipv4)), options)
+            .await
+    }
+
+    /// Update the DNS record of the given domain with the given IPv6 address, using the given record options
+    /// # Arguments
+    /// * `full_domain` - The full domain name to update, e.g. "sub.example.com" will update the "sub" subdomain of "example.com"
+    /// * `ipv6` - The IPv6 address to set
+    /// * `options` - The record options, e.g. whether the record should be created if it doesn't exist
+    /// # Returns
+    /// * `Ok(Vec<UpdateOutcome>)` - What has been done on each AAAA record, see `sync_record()`
+    /// * `Err(OvhDnsUpdaterError)` - If the update failed
+    pub async fn update_dns_ipv6_with_options(
+        &self,
+        full_domain: &str,
+        ipv6: Ipv6Addr,
+        options: &RecordOptions,
+    ) -> Result<Vec<UpdateOutcome>, OvhDnsUpdaterError> {
+        self.sync_record(full_domain, IpFamily::V6, Some(IpAddr::V6(ipv6)), options)
             .await
     }
 
@@ -163,13 +952,73 @@ impl OvhDnsUpdater {
     /// # Returns
     /// * `Ok((root_domain, sub_domain))` - If the parsing was successful
     /// * `Err(IncorrectDomainNameFormat)` - If the parsing failed
-    fn separate_root_and_sub_domain(full_domain: &str) -> Result<(&str, &str), OvhDnsUpdaterError> {
+    pub(crate) fn separate_root_and_sub_domain(
+        full_domain: &str,
+    ) -> Result<(&str, &str), OvhDnsUpdaterError> {
         let parsed_domain =
             parse_domain_name(full_domain).map_err(|_| IncorrectDomainNameFormat)?;
         let root_domain = parsed_domain.root().ok_or(IncorrectDomainNameFormat)?;
         let prefix_domain = parsed_domain.prefix().unwrap_or("");
         Ok((root_domain, prefix_domain))
     }
+
+    /// Returns the zone and the subdomain of the given full domain, in the given zone
+    /// # Arguments
+    /// * `full_domain` - The full domain name to split, e.g. "sub.home.example.co.uk"
+    /// * `zone` - The DNS zone of the record, e.g. "home.example.co.uk"
+    /// # Returns
+    /// * `Ok((zone, sub_domain))` - e.g. ("home.example.co.uk", "sub"), the subdomain is empty for the zone apex
+    /// * `Err(RecordOutsideZone)` - If the full domain isn't in the zone
+    pub(crate) fn separate_zone_and_sub_domain<'a, 'b>(
+        full_domain: &'a str,
+        zone: &'b str,
+    ) -> Result<(&'b str, &'a str), OvhDnsUpdaterError> {
+        let full_domain = full_domain.trim_end_matches('.');
+        let zone = zone.trim_end_matches('.');
+        if !zone.is_empty() && full_domain.eq_ignore_ascii_case(zone) {
+            return Ok((zone, ""));
+        }
+        if let Some(sub_domain_len) = full_domain.len().checked_sub(zone.len() + 1) {
+            if sub_domain_len > 0 && full_domain.is_char_boundary(sub_domain_len) {
+                let (sub_domain, dot_zone) = full_domain.split_at(sub_domain_len);
+                if let Some(record_zone) = dot_zone.strip_prefix('.') {
+                    if record_zone.eq_ignore_ascii_case(zone) {
+                        return Ok((zone, sub_domain));
+                    }
+                }
+            }
+        }
+        Err(RecordOutsideZone {
+            record: full_domain.to_string(),
+            zone: zone.to_string(),
+        })
+    }
+
+    /// Returns the longest of the given zones containing the full domain, and the subdomain in this zone
+    fn find_zone_of_record<'a, 'b>(
+        full_domain: &'a str,
+        zones: &'b [String],
+    ) -> Result<(&'b str, &'a str), OvhDnsUpdaterError> {
+        zones
+            .iter()
+            .filter_map(|zone| Self::separate_zone_and_sub_domain(full_domain, zone).ok())
+            .max_by_key(|(zone, _)| zone.len())
+            .ok_or_else(|| ZoneNotFound(full_domain.to_string()))
+    }
+
+    /// Returns the value percent-encoded for a query string,
+    /// only the unreserved characters of RFC 3986 are kept as they are
+    fn encode_query_value(value: &str) -> String {
+        let mut encoded = String::with_capacity(value.len());
+        for byte in value.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
 }
 
 #[cfg(test)]
@@ -195,4 +1044,65 @@ mod tests {
             Err(IncorrectDomainNameFormat)
         );
     }
+
+    #[test]
+    fn test_separate_zone_and_sub_domain() {
+        assert_eq!(
+            OvhDnsUpdater::separate_zone_and_sub_domain(
+                "sub.home.example.co.uk",
+                "home.example.co.uk"
+            )
+            .unwrap(),
+            ("home.example.co.uk", "sub")
+        );
+        assert_eq!(
+            OvhDnsUpdater::separate_zone_and_sub_domain("Home.Example.com.", "home.example.com")
+                .unwrap(),
+            ("home.example.com", "")
+        );
+        assert_eq!(
+            OvhDnsUpdater::separate_zone_and_sub_domain("myhome.example.com", "home.example.com"),
+            Err(RecordOutsideZone {
+                record: "myhome.example.com".to_string(),
+                zone: "home.example.com".to_string(),
+            })
+        );
+        assert!(OvhDnsUpdater::separate_zone_and_sub_domain("example.com", "").is_err());
+    }
+
+    #[test]
+    fn test_find_zone_of_record() {
+        let zones = vec![
+            "example.co.uk".to_string(),
+            "home.example.co.uk".to_string(),
+            "example.org".to_string(),
+        ];
+        assert_eq!(
+            OvhDnsUpdater::find_zone_of_record("nas.home.example.co.uk", &zones).unwrap(),
+            ("home.example.co.uk", "nas")
+        );
+        assert_eq!(
+            OvhDnsUpdater::find_zone_of_record("www.example.co.uk", &zones).unwrap(),
+            ("example.co.uk", "www")
+        );
+        assert_eq!(
+            OvhDnsUpdater::find_zone_of_record("example.com", &zones),
+            Err(ZoneNotFound("example.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_encode_query_value() {
+        assert_eq!(
+            OvhDnsUpdater::encode_query_value("home-1.nas_2"),
+            "home-1.nas_2"
+        );
+        assert_eq!(OvhDnsUpdater::encode_query_value("*"), "%2A");
+        assert_eq!(OvhDnsUpdater::encode_query_value("*.home"), "%2A.home");
+        assert_eq!(
+            OvhDnsUpdater::encode_query_value("a&b+c#d"),
+            "a%26b%2Bc%23d"
+        );
+        assert_eq!(OvhDnsUpdater::encode_query_value("été"), "%C3%A9t%C3%A9");
+    }
 }
@@ -0,0 +1,205 @@
+//! OVH API credentials: consumer key requests and credential details
+
+use crate::ovh_dns_updater::OvhDnsUpdater;
+use serde::{Deserialize, Serialize};
+
+/// Permission to call an OVH API path with an HTTP method, `*` matches any path part
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AccessRule {
+    pub method: String,
+    pub path: String,
+}
+
+impl AccessRule {
+    pub fn new(method: &str, path: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    /// Whether this rule allows calling the given API path with the given method
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        self.method.eq_ignore_ascii_case(method) && glob_match(&self.path, path)
+    }
+
+    /// Rules needed to keep the records of the given DNS zone up to date: read, update, and refresh the zone
+    pub fn dns_update_rules(zone: &str) -> Vec<Self> {
+        let zone_path = format!("{}{}", OvhDnsUpdater::OVH_API_DOMAIN_ZONE_PREFIX, zone);
+        vec![
+            Self::new("GET", &format!("{}/record", zone_path)),
+            Self::new("GET", &format!("{}/record/*", zone_path)),
+            Self::new("PUT", &format!("{}/record/*", zone_path)),
+            Self::new("POST", &format!("{}/refresh", zone_path)),
+        ]
+    }
+
+    /// Rules needed to read, create, update, delete the records of the given DNS zone, and refresh it
+    pub fn dns_zone_rules(zone: &str) -> Vec<Self> {
+        let zone_path = format!("{}{}", OvhDnsUpdater::OVH_API_DOMAIN_ZONE_PREFIX, zone);
+        vec![
+            Self::new("GET", &format!("{}/record", zone_path)),
+            Self::new("POST", &format!("{}/record", zone_path)),
+            Self::new("GET", &format!("{}/record/*", zone_path)),
+            Self::new("PUT", &format!("{}/record/*", zone_path)),
+            Self::new("DELETE", &format!("{}/record/*", zone_path)),
+            Self::new("POST", &format!("{}/refresh", zone_path)),
+        ]
+    }
+
+    /// Rules needed to manage the records of the given DNS zones
+    /// # Arguments
+    /// * `zones` - The zones of the records to manage, see `OvhDnsUpdater::resolve_record_zone()`,
+    ///   e.g. ["example.com", "home.example.org"]
+    pub fn for_zones<'a>(zones: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        zones.into_iter().flat_map(Self::dns_zone_rules).collect()
+    }
+}
+
+/// Matches the text against an OVH rule path, in which `*` matches any sequence of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, pattern_rest)) => {
+            let Some(text_rest) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text_rest.len())
+                .filter(|&i| text_rest.is_char_boundary(i))
+                .any(|i| glob_match(pattern_rest, &text_rest[i..]))
+        }
+    }
+}
+
+/// Answer of OVH to a consumer key request
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequest {
+    /// URL the user must visit to validate the consumer key
+    pub validation_url: String,
+    /// The new consumer key, usable once validated
+    pub consumer_key: String,
+    /// Credential status, e.g. "pendingValidation"
+    pub state: String,
+}
+
+/// Details of the credential used by the DNS updater
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OvhCredential {
+    pub credential_id: u64,
+    pub application_id: u64,
+    /// "pendingValidation", "validated", "refused" or "expired"
+    pub status: String,
+    pub creation: Option<String>,
+    pub expiration: Option<String>,
+    pub last_use: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<AccessRule>,
+}
+
+impl OvhCredential {
+    pub const STATUS_VALIDATED: &'static str = "validated";
+    pub const STATUS_PENDING_VALIDATION: &'static str = "pendingValidation";
+
+    /// Whether one of the granted rules allows calling the given API path with the given method
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        self.rules.iter().any(|rule| rule.allows(method, path))
+    }
+
+    /// Rules needed to keep the records of the given DNS zones up to date, but not granted to this credential
+    pub fn missing_update_rules<'a>(
+        &self,
+        zones: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<AccessRule> {
+        zones
+            .into_iter()
+            .flat_map(AccessRule::dns_update_rules)
+            .filter(|rule| !self.allows(&rule.method, &rule.path))
+            .collect()
+    }
+}
+
+/// OVH account owning the credential, as returned by `/me`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OvhAccount {
+    pub nichandle: String,
+    pub email: Option<String>,
+    pub firstname: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Result of the credential self-check
+#[derive(Debug, Clone, PartialEq)]
+pub struct CredentialCheck {
+    pub credential: OvhCredential,
+    /// None if the credential isn't allowed to call `GET /me`
+    pub account: Option<OvhAccount>,
+    /// Rules needed to keep the checked records up to date, but not granted
+    pub missing_rules: Vec<AccessRule>,
+}
+
+impl CredentialCheck {
+    /// Whether the credential is validated and allows updating the checked records
+    pub fn is_usable(&self) -> bool {
+        self.credential.status == OvhCredential::STATUS_VALIDATED && self.missing_rules.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_rules_for_zones() {
+        let rules = AccessRule::for_zones(["example.com", "home.example.org"]);
+        assert_eq!(rules.len(), 12);
+        assert!(rules.contains(&AccessRule::new("PUT", "/domain/zone/example.com/record/*")));
+        assert!(rules.contains(&AccessRule::new(
+            "POST",
+            "/domain/zone/home.example.org/refresh"
+        )));
+        assert!(!rules.contains(&AccessRule::new("POST", "/domain/zone/example.org/refresh")));
+    }
+
+    #[test]
+    fn test_access_rule_allows() {
+        let rule = AccessRule::new("GET", "/domain/zone/*");
+        assert!(rule.allows("GET", "/domain/zone/example.com/record/*"));
+        assert!(rule.allows("get", "/domain/zone/example.com"));
+        assert!(!rule.allows("PUT", "/domain/zone/example.com/record/*"));
+        assert!(!rule.allows("GET", "/domain/zone"));
+        let rule = AccessRule::new("PUT", "/domain/zone/*/record/*");
+        assert!(rule.allows("PUT", "/domain/zone/example.com/record/*"));
+        assert!(!rule.allows("PUT", "/domain/zone/example.com/refresh"));
+        let rule = AccessRule::new("GET", "/domain/zone/example.com/record/12");
+        assert!(!rule.allows("GET", "/domain/zone/example.com/record/*"));
+    }
+
+    #[test]
+    fn test_missing_update_rules() {
+        let credential = OvhCredential {
+            credential_id: 1,
+            application_id: 2,
+            status: OvhCredential::STATUS_VALIDATED.to_string(),
+            creation: None,
+            expiration: None,
+            last_use: None,
+            rules: vec![
+                AccessRule::new("GET", "/domain/zone/*"),
+                AccessRule::new("PUT", "/domain/zone/example.com/record/*"),
+            ],
+        };
+        assert_eq!(
+            credential.missing_update_rules(["example.com"]),
+            vec![AccessRule::new("POST", "/domain/zone/example.com/refresh")]
+        );
+        assert_eq!(
+            credential.missing_update_rules(["example.org"]),
+            vec![
+                AccessRule::new("PUT", "/domain/zone/example.org/record/*"),
+                AccessRule::new("POST", "/domain/zone/example.org/refresh"),
+            ]
+        );
+    }
+}
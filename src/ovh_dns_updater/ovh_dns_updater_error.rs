@@ -1,17 +1,40 @@
+use crate::ovh::ovh_api_error::OvhApiError;
+use crate::ovh_dns_updater::dns_record::DnsRecordType;
 use thiserror::Error;
 
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum OvhDnsUpdaterError {
-    #[error("Failed to login into OVH account: maybe application doesn't exist or credentials are wrong. Otherwise check network connection")]
-    OvhLoginError,
+    #[error("Invalid OVH endpoint \"{0}\": expected a known endpoint name or an https:// API URL")]
+    InvalidOvhEndpoint(String),
     #[error("Incorrect domain name format")]
     IncorrectDomainNameFormat,
-    #[error("Domain retrieving error, check domain name zone and network connection")]
-    OvhDomainRetrievingError,
+    #[error("Domain retrieving error, check domain name zone and network connection: {0}")]
+    OvhDomainRetrievingError(OvhApiError),
     #[error("Incorrect response format from OVH API")]
     IncorrectAPIResponseFormat,
     #[error("Domain zone entry doesn't exist, check domain or subdomain name")]
     DomainZoneEntryDoesntExist,
-    #[error("Domain update error, check domain name zone and network connection")]
-    OvhDomainUpdatingError,
+    #[error("Domain update error, check domain name zone and network connection: {0}")]
+    OvhDomainUpdatingError(OvhApiError),
+    #[error("Domain creation error, check domain name zone and network connection: {0}")]
+    OvhDomainCreatingError(OvhApiError),
+    #[error("Domain deletion error, check domain name zone and network connection: {0}")]
+    OvhDomainDeletingError(OvhApiError),
+    #[error("Several records match the domain and type (IDs {ids:?}), choose a duplicate records policy")]
+    AmbiguousRecord { ids: Vec<usize> },
+    #[error("DNS zone refresh error, changes may not be published yet: {0}")]
+    OvhZoneRefreshError(OvhApiError),
+    #[error("OVH credential error, check application key and secret: {0}")]
+    OvhCredentialError(OvhApiError),
+    #[error("Invalid {record_type} record target: {reason}")]
+    InvalidRecordTarget {
+        record_type: DnsRecordType,
+        reason: String,
+    },
+    #[error("Invalid zone state file: {0}")]
+    InvalidZoneStateFile(String),
+    #[error("Record {record} doesn't belong to zone {zone}")]
+    RecordOutsideZone { record: String, zone: String },
+    #[error("No DNS zone of the OVH account contains {0}")]
+    ZoneNotFound(String),
 }
@@ -9,6 +9,6 @@ pub(crate) struct OvhDomainResponseObj {
     pub(crate) zone: String,
     pub(crate) fieldType: String,
     pub(crate) id: usize,
-    pub(crate) ttl: usize,
+    pub(crate) ttl: u32,
     pub(crate) target: String,
 }
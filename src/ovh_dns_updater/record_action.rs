@@ -0,0 +1,27 @@
+//! Describes what has been done on a DNS record
+
+use std::fmt::{Display, Formatter};
+
+/// Action performed on a DNS record during an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordAction {
+    /// The record didn't exist and has been created
+    Created,
+    /// The existing record has been updated
+    Updated,
+    /// The record was already up to date, nothing has been sent to OVH
+    Unchanged,
+    /// The record has been deleted
+    Deleted,
+}
+
+impl Display for RecordAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RecordAction::Created => "created",
+            RecordAction::Updated => "updated",
+            RecordAction::Unchanged => "unchanged",
+            RecordAction::Deleted => "deleted",
+        })
+    }
+}
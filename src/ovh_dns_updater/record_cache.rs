@@ -0,0 +1,186 @@
+//! Records read from the OVH API, kept for a short time so that they aren't fetched twice,
+//! e.g. when several records of a run live in the same zone
+
+use crate::ovh_dns_updater::dns_record::{DnsRecord, DnsRecordType};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+struct CachedEntry<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+pub(crate) struct RecordCache {
+    lifetime: Duration,
+    /// Record IDs listings, by zone and listing API path
+    record_ids: HashMap<(String, String), CachedEntry<Vec<usize>>>,
+    /// Records, by zone and ID
+    records: HashMap<(String, usize), CachedEntry<DnsRecord>>,
+}
+
+impl RecordCache {
+    /// Default time during which a record read from OVH is reused
+    pub(crate) const DEFAULT_LIFETIME: Duration = Duration::from_secs(60);
+
+    pub(crate) fn new(lifetime: Duration) -> Self {
+        Self {
+            lifetime,
+            record_ids: HashMap::new(),
+            records: HashMap::new(),
+        }
+    }
+
+    /// Set how long entries are reused, `Duration::ZERO` disables the cache
+    pub(crate) fn set_lifetime(&mut self, lifetime: Duration) {
+        self.lifetime = lifetime;
+        self.clear();
+    }
+
+    fn is_fresh<T>(&self, cached_entry: &CachedEntry<T>) -> bool {
+        cached_entry.fetched_at.elapsed() < self.lifetime
+    }
+
+    pub(crate) fn record_ids(&self, zone: &str, listing_path: &str) -> Option<Vec<usize>> {
+        self.record_ids
+            .get(&(zone.to_string(), listing_path.to_string()))
+            .filter(|cached_entry| self.is_fresh(cached_entry))
+            .map(|cached_entry| cached_entry.value.clone())
+    }
+
+    pub(crate) fn insert_record_ids(&mut self, zone: &str, listing_path: &str, ids: &[usize]) {
+        if self.lifetime.is_zero() {
+            return;
+        }
+        self.record_ids.insert(
+            (zone.to_string(), listing_path.to_string()),
+            CachedEntry {
+                value: ids.to_vec(),
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Matching record IDs found from the cached listing of the whole zone,
+    /// None unless the listing and all its records are cached
+    pub(crate) fn matching_record_ids(
+        &self,
+        zone: &str,
+        zone_listing_path: &str,
+        record_type: Option<DnsRecordType>,
+        sub_domain: Option<&str>,
+    ) -> Option<Vec<usize>> {
+        let zone_record_ids = self.record_ids(zone, zone_listing_path)?;
+        let mut matching_record_ids = Vec::new();
+        for record_id in zone_record_ids {
+            let dns_record = self.record(zone, record_id)?;
+            if record_type.is_none_or(|record_type| record_type == dns_record.record_type)
+                && sub_domain.is_none_or(|sub_domain| {
+                    sub_domain.eq_ignore_ascii_case(&dns_record.sub_domain)
+                })
+            {
+                matching_record_ids.push(record_id);
+            }
+        }
+        Some(matching_record_ids)
+    }
+
+    pub(crate) fn record(&self, zone: &str, record_id: usize) -> Option<DnsRecord> {
+        self.records
+            .get(&(zone.to_string(), record_id))
+            .filter(|cached_entry| self.is_fresh(cached_entry))
+            .map(|cached_entry| cached_entry.value.clone())
+    }
+
+    pub(crate) fn insert_record(&mut self, record_id: usize, dns_record: &DnsRecord) {
+        if self.lifetime.is_zero() {
+            return;
+        }
+        self.records.insert(
+            (dns_record.zone.clone(), record_id),
+            CachedEntry {
+                value: dns_record.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget everything about the zone, called when one of its records is modified
+    pub(crate) fn invalidate_zone(&mut self, zone: &str) {
+        self.record_ids
+            .retain(|(cached_zone, _), _| cached_zone != zone);
+        self.records
+            .retain(|(cached_zone, _), _| cached_zone != zone);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.record_ids.clear();
+        self.records.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_cache() {
+        let dns_record =
+            DnsRecord::new("example.com", "home", DnsRecordType::A, "1.1.1.1", 0).unwrap();
+        let mut record_cache = RecordCache::new(RecordCache::DEFAULT_LIFETIME);
+        record_cache.insert_record_ids("example.com", "/record?fieldType=A", &[1, 2]);
+        record_cache.insert_record(1, &dns_record);
+        record_cache.insert_record(
+            3,
+            &DnsRecord {
+                zone: "example.org".to_string(),
+                ..dns_record.clone()
+            },
+        );
+        assert_eq!(
+            record_cache.record_ids("example.com", "/record?fieldType=A"),
+            Some(vec![1, 2])
+        );
+        assert_eq!(record_cache.record_ids("example.com", "/record"), None);
+        assert_eq!(
+            record_cache.record("example.com", 1),
+            Some(dns_record.clone())
+        );
+        // Record 2 of the zone listing isn't cached
+        assert_eq!(
+            record_cache.matching_record_ids("example.com", "/record?fieldType=A", None, None),
+            None
+        );
+        record_cache.insert_record(
+            2,
+            &DnsRecord {
+                record_type: DnsRecordType::Aaaa,
+                target: "::1".to_string(),
+                ..dns_record.clone()
+            },
+        );
+        assert_eq!(
+            record_cache.matching_record_ids(
+                "example.com",
+                "/record?fieldType=A",
+                Some(DnsRecordType::Aaaa),
+                Some("HOME")
+            ),
+            Some(vec![2])
+        );
+
+        record_cache.invalidate_zone("example.com");
+        assert_eq!(
+            record_cache.record_ids("example.com", "/record?fieldType=A"),
+            None
+        );
+        assert_eq!(record_cache.record("example.com", 1), None);
+        assert!(record_cache.record("example.org", 3).is_some());
+
+        record_cache.set_lifetime(Duration::ZERO);
+        record_cache.insert_record_ids("example.com", "/record?fieldType=A", &[1, 2]);
+        assert_eq!(
+            record_cache.record_ids("example.com", "/record?fieldType=A"),
+            None
+        );
+    }
+}
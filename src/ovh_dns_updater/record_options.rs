@@ -0,0 +1,224 @@
+//! Per-record settings used when updating a DNS record
+
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// What to do when several records have the same subdomain and type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateRecordPolicy {
+    /// Fail with `AmbiguousRecord`
+    #[default]
+    Fail,
+    /// Update all the records, e.g. for round-robin setups
+    UpdateAll,
+    /// Update the record with the lowest ID and delete the other ones
+    UpdateFirstDeleteOthers,
+}
+
+impl DuplicateRecordPolicy {
+    const FAIL_STR: &'static str = "fail";
+    const UPDATE_ALL_STR: &'static str = "update-all";
+    const UPDATE_FIRST_DELETE_OTHERS_STR: &'static str = "update-first-delete-others";
+}
+
+impl Display for DuplicateRecordPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DuplicateRecordPolicy::Fail => Self::FAIL_STR,
+            DuplicateRecordPolicy::UpdateAll => Self::UPDATE_ALL_STR,
+            DuplicateRecordPolicy::UpdateFirstDeleteOthers => Self::UPDATE_FIRST_DELETE_OTHERS_STR,
+        })
+    }
+}
+
+impl FromStr for DuplicateRecordPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Self::FAIL_STR => Ok(DuplicateRecordPolicy::Fail),
+            Self::UPDATE_ALL_STR => Ok(DuplicateRecordPolicy::UpdateAll),
+            Self::UPDATE_FIRST_DELETE_OTHERS_STR => {
+                Ok(DuplicateRecordPolicy::UpdateFirstDeleteOthers)
+            }
+            _ => Err(format!(
+                "expected \"{}\", \"{}\" or \"{}\"",
+                Self::FAIL_STR,
+                Self::UPDATE_ALL_STR,
+                Self::UPDATE_FIRST_DELETE_OTHERS_STR
+            )),
+        }
+    }
+}
+
+/// TTL to set on a record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TtlPolicy {
+    /// Keep the TTL of existing records, records are created with the zone default TTL
+    #[default]
+    Preserve,
+    /// Use the default TTL of the zone
+    ZoneDefault,
+    /// Use the given TTL, in seconds
+    Seconds(u32),
+}
+
+impl TtlPolicy {
+    const PRESERVE_STR: &'static str = "preserve";
+    const ZONE_DEFAULT_STR: &'static str = "default";
+
+    /// TTL to send to OVH when updating a record, None to leave it unchanged.
+    /// OVH represents the zone default TTL as 0.
+    pub(crate) fn update_ttl(&self) -> Option<u32> {
+        match self {
+            TtlPolicy::Preserve => None,
+            TtlPolicy::ZoneDefault => Some(0),
+            TtlPolicy::Seconds(seconds) => Some(*seconds),
+        }
+    }
+
+    /// TTL to send to OVH when creating a record
+    pub(crate) fn creation_ttl(&self) -> u32 {
+        self.update_ttl().unwrap_or(0)
+    }
+}
+
+impl Display for TtlPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TtlPolicy::Preserve => f.write_str(Self::PRESERVE_STR),
+            TtlPolicy::ZoneDefault => f.write_str(Self::ZONE_DEFAULT_STR),
+            TtlPolicy::Seconds(seconds) => write!(f, "{}", seconds),
+        }
+    }
+}
+
+impl FromStr for TtlPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Self::PRESERVE_STR => Ok(TtlPolicy::Preserve),
+            Self::ZONE_DEFAULT_STR => Ok(TtlPolicy::ZoneDefault),
+            _ => s.parse::<u32>().map(TtlPolicy::Seconds).map_err(|_| {
+                format!(
+                    "expected \"{}\", \"{}\" or a number of seconds",
+                    Self::PRESERVE_STR,
+                    Self::ZONE_DEFAULT_STR
+                )
+            }),
+        }
+    }
+}
+
+/// What to do with a record when the public address of its family (IPv4 or IPv6) can't be retrieved,
+/// e.g. because the IPv6 connectivity disappeared
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LostAddressPolicy {
+    /// Leave the record unchanged
+    #[default]
+    Keep,
+    /// Delete the record, so that clients stop using the dead address
+    Delete,
+    /// Point the record to the given address, of the same family as the record
+    Fallback(IpAddr),
+}
+
+impl LostAddressPolicy {
+    const KEEP_STR: &'static str = "keep";
+    const DELETE_STR: &'static str = "delete";
+}
+
+impl Display for LostAddressPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LostAddressPolicy::Keep => f.write_str(Self::KEEP_STR),
+            LostAddressPolicy::Delete => f.write_str(Self::DELETE_STR),
+            LostAddressPolicy::Fallback(fallback_ip) => write!(f, "{}", fallback_ip),
+        }
+    }
+}
+
+impl FromStr for LostAddressPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Self::KEEP_STR => Ok(LostAddressPolicy::Keep),
+            Self::DELETE_STR => Ok(LostAddressPolicy::Delete),
+            _ => s
+                .parse::<IpAddr>()
+                .map(LostAddressPolicy::Fallback)
+                .map_err(|_| {
+                    format!(
+                        "expected \"{}\", \"{}\" or a fallback IP address",
+                        Self::KEEP_STR,
+                        Self::DELETE_STR
+                    )
+                }),
+        }
+    }
+}
+
+/// Options applied when updating a single DNS record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordOptions {
+    /// Create the record if it doesn't exist yet, instead of failing with `DomainZoneEntryDoesntExist`
+    pub create_if_missing: bool,
+    /// TTL of the record, a record whose TTL doesn't match is updated
+    pub ttl: TtlPolicy,
+    /// What to do when several records have the same subdomain and type
+    pub duplicates: DuplicateRecordPolicy,
+    /// DNS zone of the record, e.g. "home.example.co.uk" for a delegated sub-zone.
+    /// By default the zone is guessed from the public suffix list.
+    pub zone: Option<String>,
+    /// Find the zone of the record among the zones of the OVH account (longest match), when `zone` isn't set
+    pub discover_zone: bool,
+    /// What to do with the record when the public address of its family can't be retrieved
+    pub lost_address: LostAddressPolicy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_record_policy_from_str() {
+        for duplicate_record_policy in [
+            DuplicateRecordPolicy::Fail,
+            DuplicateRecordPolicy::UpdateAll,
+            DuplicateRecordPolicy::UpdateFirstDeleteOthers,
+        ] {
+            assert_eq!(
+                duplicate_record_policy
+                    .to_string()
+                    .parse::<DuplicateRecordPolicy>(),
+                Ok(duplicate_record_policy)
+            );
+        }
+        assert!("keep".parse::<DuplicateRecordPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_ttl_policy_from_str() {
+        assert_eq!("preserve".parse::<TtlPolicy>(), Ok(TtlPolicy::Preserve));
+        assert_eq!("default".parse::<TtlPolicy>(), Ok(TtlPolicy::ZoneDefault));
+        assert_eq!("3600".parse::<TtlPolicy>(), Ok(TtlPolicy::Seconds(3600)));
+        assert!("-1".parse::<TtlPolicy>().is_err());
+        assert_eq!(TtlPolicy::Seconds(60).to_string(), "60");
+    }
+
+    #[test]
+    fn test_lost_address_policy_from_str() {
+        assert_eq!("keep".parse(), Ok(LostAddressPolicy::Keep));
+        assert_eq!("delete".parse(), Ok(LostAddressPolicy::Delete));
+        let fallback_policy = "2001:db8::1".parse::<LostAddressPolicy>().unwrap();
+        assert_eq!(
+            fallback_policy,
+            LostAddressPolicy::Fallback("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(fallback_policy.to_string(), "2001:db8::1");
+        assert!("drop".parse::<LostAddressPolicy>().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Describes the result of a DNS record update
+
+use crate::ovh_dns_updater::dns_record::{
+    describe_record_content, full_domain, DnsRecord, DnsRecordType,
+};
+use crate::ovh_dns_updater::record_action::RecordAction;
+use crate::ovh_dns_updater::zone_refresh::ZoneRefreshStatus;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Result of the synchronization of a single DNS record
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateOutcome {
+    /// OVH ID of the record, the new ID if it has been created
+    pub record_id: Option<usize>,
+    /// The DNS zone of the record, e.g. "example.com"
+    pub zone: String,
+    /// The subdomain in the zone, e.g. "www", empty for the zone apex
+    pub sub_domain: String,
+    pub record_type: DnsRecordType,
+    /// Target before the synchronization, None if the record has been created
+    pub old_target: Option<String>,
+    /// Target after the synchronization, None if the record has been deleted
+    pub new_target: Option<String>,
+    /// TTL before the synchronization, None if the record has been created
+    pub old_ttl: Option<u32>,
+    /// TTL after the synchronization, None if the record has been deleted
+    pub new_ttl: Option<u32>,
+    /// Whether the record has been created, updated, deleted or left unchanged
+    pub action: RecordAction,
+    /// What happened to the zone refresh that publishes the change
+    pub zone_refresh: ZoneRefreshStatus,
+    /// Time taken by the whole synchronization, including the zone refresh
+    pub duration: Duration,
+}
+
+impl UpdateOutcome {
+    /// Outcome of an action on a record, the zone refresh and duration are filled in once the synchronization is done
+    /// # Arguments
+    /// * `action` - What has been done on the record
+    /// * `old_record` - The record before the action, None if it has been created
+    /// * `new_record` - The record after the action, None if it has been deleted
+    pub(crate) fn new(
+        action: RecordAction,
+        old_record: Option<&DnsRecord>,
+        new_record: Option<&DnsRecord>,
+    ) -> Self {
+        // At least one of them is given
+        let record = new_record.or(old_record).unwrap();
+        Self {
+            record_id: record.id,
+            zone: record.zone.clone(),
+            sub_domain: record.sub_domain.clone(),
+            record_type: record.record_type,
+            old_target: old_record.map(|old_record| old_record.target.clone()),
+            new_target: new_record.map(|new_record| new_record.target.clone()),
+            old_ttl: old_record.map(|old_record| old_record.ttl),
+            new_ttl: new_record.map(|new_record| new_record.ttl),
+            action,
+            zone_refresh: ZoneRefreshStatus::NotNeeded,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Whether the record has been modified on OVH
+    pub fn is_change(&self) -> bool {
+        self.action != RecordAction::Unchanged
+    }
+}
+
+impl Display for UpdateOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} ",
+            self.action,
+            full_domain(&self.zone, &self.sub_domain),
+            self.record_type
+        )?;
+        // The old content is set unless the record has been created, the new one unless it has been deleted
+        let old_content = || {
+            describe_record_content(
+                self.old_target.as_deref().unwrap_or_default(),
+                self.old_ttl.unwrap_or_default(),
+            )
+        };
+        let new_content = || {
+            describe_record_content(
+                self.new_target.as_deref().unwrap_or_default(),
+                self.new_ttl.unwrap_or_default(),
+            )
+        };
+        match self.action {
+            RecordAction::Created | RecordAction::Unchanged => f.write_str(&new_content())?,
+            RecordAction::Updated => write!(f, "{} -> {}", old_content(), new_content())?,
+            RecordAction::Deleted => f.write_str(&old_content())?,
+        }
+        if let Some(record_id) = self.record_id {
+            write!(f, ", record {}", record_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_outcome() {
+        let old_record = DnsRecord {
+            id: Some(1),
+            ..DnsRecord::new("example.com", "home", DnsRecordType::A, "1.1.1.1", 0).unwrap()
+        };
+        let new_record = DnsRecord {
+            target: "2.2.2.2".to_string(),
+            ttl: 300,
+            ..old_record.clone()
+        };
+        let update_outcome =
+            UpdateOutcome::new(RecordAction::Updated, Some(&old_record), Some(&new_record));
+        assert_eq!(update_outcome.record_id, Some(1));
+        assert_eq!(update_outcome.old_target.as_deref(), Some("1.1.1.1"));
+        assert_eq!(update_outcome.new_ttl, Some(300));
+        assert!(update_outcome.is_change());
+        assert_eq!(
+            update_outcome.to_string(),
+            "updated home.example.com A 1.1.1.1 (TTL 0) -> 2.2.2.2 (TTL 300), record 1"
+        );
+
+        let delete_outcome = UpdateOutcome::new(RecordAction::Deleted, Some(&old_record), None);
+        assert_eq!(delete_outcome.new_target, None);
+        assert_eq!(
+            delete_outcome.to_string(),
+            "deleted home.example.com A 1.1.1.1 (TTL 0), record 1"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Changes needed to bring a DNS zone to its desired state
+
+use crate::ovh_dns_updater::dns_record::{describe_record_content, DnsRecord, DnsRecordType};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Change of a single record of the zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordChange {
+    Create(DnsRecord),
+    Update {
+        current: DnsRecord,
+        desired: DnsRecord,
+    },
+    Delete(DnsRecord),
+}
+
+impl RecordChange {
+    /// Deletions are applied first, so that a new record doesn't conflict with an old one, e.g. a CNAME
+    pub(crate) fn apply_order(&self) -> u8 {
+        match self {
+            RecordChange::Delete(_) => 0,
+            RecordChange::Update { .. } => 1,
+            RecordChange::Create(_) => 2,
+        }
+    }
+}
+
+impl Display for RecordChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordChange::Create(desired) => write!(
+                f,
+                "+ create {} {} {}",
+                desired.full_domain(),
+                desired.record_type,
+                describe_record_content(&desired.target, desired.ttl)
+            ),
+            RecordChange::Update { current, desired } => write!(
+                f,
+                "~ update {} {} {} -> {}",
+                current.full_domain(),
+                current.record_type,
+                describe_record_content(&current.target, current.ttl),
+                describe_record_content(&desired.target, desired.ttl)
+            ),
+            RecordChange::Delete(current) => write!(
+                f,
+                "- delete {} {} {}",
+                current.full_domain(),
+                current.record_type,
+                describe_record_content(&current.target, current.ttl)
+            ),
+        }
+    }
+}
+
+/// Changes needed to bring a DNS zone to its desired state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZonePlan {
+    /// The DNS zone, e.g. "example.com"
+    pub zone: String,
+    pub changes: Vec<RecordChange>,
+}
+
+impl ZonePlan {
+    /// Compare the live records of the zone with the desired ones.
+    /// Records are matched by subdomain and type, then by target, so that records sharing a subdomain and type are supported.
+    /// # Arguments
+    /// * `zone` - The DNS zone, e.g. "example.com"
+    /// * `live_records` - The records currently in the zone
+    /// * `desired_records` - The desired records with their TTL, None if the TTL of an existing record is kept
+    /// * `prune` - Whether live records that aren't desired are deleted, the NS records of the zone apex never are
+    pub fn compute(
+        zone: &str,
+        live_records: Vec<DnsRecord>,
+        desired_records: Vec<(DnsRecord, Option<u32>)>,
+        prune: bool,
+    ) -> Self {
+        type RecordGroup = (Vec<DnsRecord>, Vec<(DnsRecord, Option<u32>)>);
+        let mut record_groups: BTreeMap<(String, DnsRecordType), RecordGroup> = BTreeMap::new();
+        for live_record in live_records {
+            record_groups
+                .entry((
+                    live_record.sub_domain.to_lowercase(),
+                    live_record.record_type,
+                ))
+                .or_default()
+                .0
+                .push(live_record);
+        }
+        for desired_record in desired_records {
+            record_groups
+                .entry((
+                    desired_record.0.sub_domain.to_lowercase(),
+                    desired_record.0.record_type,
+                ))
+                .or_default()
+                .1
+                .push(desired_record);
+        }
+
+        let mut changes = Vec::new();
+        for ((sub_domain, record_type), (mut live_records, desired_records)) in record_groups {
+            let mut unmatched_desired_records = Vec::new();
+            for (desired_record, desired_ttl) in desired_records {
+                let desired_target = desired_record.normalized_target();
+                match live_records
+                    .iter()
+                    .position(|live_record| live_record.normalized_target() == desired_target)
+                {
+                    Some(position) => {
+                        let live_record = live_records.remove(position);
+                        if desired_ttl.is_some_and(|ttl| ttl != live_record.ttl) {
+                            changes.push(Self::update_change(
+                                live_record,
+                                desired_record,
+                                desired_ttl,
+                            ));
+                        }
+                    }
+                    None => unmatched_desired_records.push((desired_record, desired_ttl)),
+                }
+            }
+            let is_apex_ns = sub_domain.is_empty() && record_type == DnsRecordType::Ns;
+            // Records that aren't desired are only modified when they would be deleted anyway,
+            // then they are updated in place rather than deleted and created again
+            let prunable_records = if prune && !is_apex_ns {
+                live_records
+            } else {
+                Vec::new()
+            };
+            let mut prunable_records = prunable_records.into_iter();
+            for (desired_record, desired_ttl) in unmatched_desired_records {
+                match prunable_records.next() {
+                    Some(live_record) => changes.push(Self::update_change(
+                        live_record,
+                        desired_record,
+                        desired_ttl,
+                    )),
+                    None => changes.push(RecordChange::Create(desired_record)),
+                }
+            }
+            changes.extend(prunable_records.map(RecordChange::Delete));
+        }
+        Self {
+            zone: zone.to_string(),
+            changes,
+        }
+    }
+
+    fn update_change(
+        live_record: DnsRecord,
+        mut desired_record: DnsRecord,
+        desired_ttl: Option<u32>,
+    ) -> RecordChange {
+        desired_record.id = live_record.id;
+        desired_record.ttl = desired_ttl.unwrap_or(live_record.ttl);
+        RecordChange::Update {
+            current: live_record,
+            desired: desired_record,
+        }
+    }
+
+    /// Whether the zone is already in its desired state
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_record(
+        id: usize,
+        sub_domain: &str,
+        record_type: DnsRecordType,
+        target: &str,
+    ) -> DnsRecord {
+        DnsRecord {
+            id: Some(id),
+            ..DnsRecord::new("example.com", sub_domain, record_type, target, 0).unwrap()
+        }
+    }
+
+    fn desired_record(
+        sub_domain: &str,
+        record_type: DnsRecordType,
+        target: &str,
+        ttl: Option<u32>,
+    ) -> (DnsRecord, Option<u32>) {
+        let dns_record = DnsRecord::new(
+            "example.com",
+            sub_domain,
+            record_type,
+            target,
+            ttl.unwrap_or(0),
+        )
+        .unwrap();
+        (dns_record, ttl)
+    }
+
+    #[test]
+    fn test_compute_zone_plan() {
+        let live_records = vec![
+            live_record(1, "home", DnsRecordType::A, "1.1.1.1"),
+            live_record(2, "", DnsRecordType::Mx, "10 mx1.example.com."),
+            live_record(3, "", DnsRecordType::Mx, "20 mx2.example.com."),
+            live_record(4, "old", DnsRecordType::Txt, "\"obsolete\""),
+            live_record(5, "", DnsRecordType::Ns, "dns1.ovh.net."),
+        ];
+        let desired_records = vec![
+            desired_record("home", DnsRecordType::A, "2.2.2.2", None),
+            desired_record("", DnsRecordType::Mx, "20 mx2.example.com.", None),
+            desired_record("", DnsRecordType::Mx, "10 mx1.example.com.", Some(300)),
+            desired_record("www", DnsRecordType::Cname, "home", None),
+        ];
+
+        let zone_plan = ZonePlan::compute(
+            "example.com",
+            live_records.clone(),
+            desired_records.clone(),
+            false,
+        );
+        assert_eq!(zone_plan.changes.len(), 3);
+        assert!(zone_plan
+            .changes
+            .contains(&RecordChange::Create(desired_records[0].0.clone())));
+        assert!(zone_plan.changes.contains(&RecordChange::Update {
+            current: live_records[1].clone(),
+            desired: DnsRecord {
+                id: Some(2),
+                ..desired_records[2].0.clone()
+            },
+        }));
+        assert!(zone_plan
+            .changes
+            .contains(&RecordChange::Create(desired_records[3].0.clone())));
+
+        let zone_plan = ZonePlan::compute(
+            "example.com",
+            live_records.clone(),
+            desired_records.clone(),
+            true,
+        );
+        assert_eq!(zone_plan.changes.len(), 4);
+        assert!(zone_plan.changes.contains(&RecordChange::Update {
+            current: live_records[0].clone(),
+            desired: DnsRecord {
+                id: Some(1),
+                ..desired_records[0].0.clone()
+            },
+        }));
+        assert!(zone_plan
+            .changes
+            .contains(&RecordChange::Delete(live_records[3].clone())));
+    }
+
+    #[test]
+    fn test_compute_zone_plan_keeps_unlisted_records() {
+        // The second TXT record is kept by hand, it isn't in the desired state
+        let live_records = vec![
+            live_record(1, "", DnsRecordType::Txt, "\"v=spf1 -all\""),
+            live_record(
+                2,
+                "",
+                DnsRecordType::Txt,
+                "\"google-site-verification=abc\"",
+            ),
+        ];
+        let desired_records = vec![
+            desired_record("", DnsRecordType::Txt, "v=spf1 -all", None),
+            desired_record("", DnsRecordType::Txt, "v=DMARC1; p=none", None),
+        ];
+
+        let zone_plan = ZonePlan::compute(
+            "example.com",
+            live_records.clone(),
+            desired_records.clone(),
+            false,
+        );
+        assert_eq!(
+            zone_plan.changes,
+            vec![RecordChange::Create(desired_records[1].0.clone())]
+        );
+
+        let zone_plan =
+            ZonePlan::compute("example.com", live_records.clone(), desired_records, true);
+        assert!(matches!(
+            &zone_plan.changes[..],
+            [RecordChange::Update { current, .. }] if *current == live_records[1]
+        ));
+    }
+
+    #[test]
+    fn test_compute_zone_plan_normalized_targets() {
+        let live_records = vec![
+            live_record(1, "home", DnsRecordType::Aaaa, "2001:db8::1"),
+            live_record(2, "www", DnsRecordType::Cname, "home.example.com."),
+            live_record(3, "", DnsRecordType::Mx, "10 mx1.example.com."),
+            live_record(4, "", DnsRecordType::Txt, "\"v=spf1 -all\""),
+            live_record(5, "Mail", DnsRecordType::A, "1.2.3.4"),
+        ];
+        let desired_records = vec![
+            desired_record("home", DnsRecordType::Aaaa, "2001:0DB8:0:0::1", None),
+            desired_record("www", DnsRecordType::Cname, "Home", None),
+            desired_record("", DnsRecordType::Mx, "10 MX1.example.com", None),
+            desired_record("", DnsRecordType::Txt, "v=spf1 -all", None),
+            desired_record("mail", DnsRecordType::A, "1.2.3.4", None),
+        ];
+        for desired_record in desired_records {
+            let zone_plan = ZonePlan::compute(
+                "example.com",
+                live_records.clone(),
+                vec![desired_record.clone()],
+                false,
+            );
+            assert!(zone_plan.is_empty(), "{:?}", desired_record.0);
+        }
+    }
+
+    #[test]
+    fn test_display_record_change() {
+        let change = RecordChange::Update {
+            current: live_record(1, "home", DnsRecordType::A, "1.1.1.1"),
+            desired: live_record(1, "home", DnsRecordType::A, "2.2.2.2"),
+        };
+        assert_eq!(
+            change.to_string(),
+            "~ update home.example.com A 1.1.1.1 (TTL 0) -> 2.2.2.2 (TTL 0)"
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! OVH doesn't publish DNS zone changes until the zone is refreshed.
+//! This module describes when the refresh is done and how it went.
+
+use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError;
+
+/// When the DNS zone should be refreshed after a record has been modified
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZoneRefreshPolicy {
+    /// Refresh the zone right after each record modification
+    #[default]
+    Immediate,
+    /// Remember the modified zones, they are refreshed once each when calling `refresh_pending_zones()`
+    Deferred,
+    /// Never refresh the zone, changes will be published by OVH later or by another refresh
+    Skip,
+}
+
+/// Result of the zone refresh following a record modification
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneRefreshStatus {
+    /// The zone has been refreshed
+    Refreshed,
+    /// The zone will be refreshed when calling `refresh_pending_zones()`
+    Pending,
+    /// The zone hasn't been refreshed, as requested by the refresh policy
+    Skipped,
+    /// Nothing has been modified, the zone doesn't need a refresh
+    NotNeeded,
+    /// The refresh request failed, the record change may not be published yet
+    Failed(OvhDnsUpdaterError),
+}
@@ -0,0 +1,242 @@
+//! Desired state of DNS zones, described in a TOML file
+
+use crate::ovh_dns_updater::dns_record::{DnsRecord, DnsRecordType};
+use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError;
+use crate::ovh_dns_updater::ovh_dns_updater_error::OvhDnsUpdaterError::*;
+use serde::Deserialize;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Content of a zone state file:
+/// ```toml
+/// [[zone]]
+/// name = "example.com"
+/// prune = true
+///
+/// [[zone.record]]
+/// name = "home"
+/// type = "A"
+/// ttl = 300
+/// use_public_ip = true
+///
+/// [[zone.record]]
+/// name = "@"
+/// type = "MX"
+/// target = "10 mx1.example.com."
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneStateFile {
+    #[serde(rename = "zone", default)]
+    pub zones: Vec<ZoneState>,
+}
+
+/// Desired records of a DNS zone
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneState {
+    /// The DNS zone, e.g. "example.com"
+    pub name: String,
+    /// Delete the records of the zone that aren't described, except the NS records of the zone apex
+    #[serde(default)]
+    pub prune: bool,
+    #[serde(rename = "record", default)]
+    pub records: Vec<DesiredRecord>,
+}
+
+/// Desired record of a DNS zone, its target is either given or the current public IP address
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DesiredRecord {
+    /// The subdomain in the zone, e.g. "www", "" or "@" for the zone apex
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub record_type: DnsRecordType,
+    /// TTL in seconds, 0 means the zone default TTL, None keeps the TTL of an existing record
+    pub ttl: Option<u32>,
+    pub target: Option<String>,
+    /// Use the current public IPv4 (A record) or IPv6 (AAAA record) address as target
+    #[serde(default)]
+    pub use_public_ip: bool,
+}
+
+impl ZoneStateFile {
+    /// Parse a zone state file
+    /// # Arguments
+    /// * `toml_content` - The content of the TOML file
+    /// # Returns
+    /// * `Ok(ZoneStateFile)` - If the file is valid
+    /// * `Err(InvalidZoneStateFile)` - If the file can't be parsed, or a record has an invalid target, no target, or both a target and `use_public_ip`
+    pub fn from_toml(toml_content: &str) -> Result<Self, OvhDnsUpdaterError> {
+        let zone_state_file: Self =
+            toml::from_str(toml_content).map_err(|e| InvalidZoneStateFile(e.to_string()))?;
+        for zone in &zone_state_file.zones {
+            for record in &zone.records {
+                record.check_target_source(&zone.name)?;
+            }
+        }
+        Ok(zone_state_file)
+    }
+
+    /// Whether a record uses the current public IPv4 address
+    pub fn needs_public_ipv4(&self) -> bool {
+        self.uses_public_ip_for(DnsRecordType::A)
+    }
+
+    /// Whether a record uses the current public IPv6 address
+    pub fn needs_public_ipv6(&self) -> bool {
+        self.uses_public_ip_for(DnsRecordType::Aaaa)
+    }
+
+    fn uses_public_ip_for(&self, record_type: DnsRecordType) -> bool {
+        self.zones
+            .iter()
+            .flat_map(|zone| &zone.records)
+            .any(|record| record.use_public_ip && record.record_type == record_type)
+    }
+}
+
+impl DesiredRecord {
+    /// The subdomain in the zone, "" for the zone apex
+    pub fn sub_domain(&self) -> &str {
+        if self.name == "@" {
+            ""
+        } else {
+            &self.name
+        }
+    }
+
+    fn check_target_source(&self, zone: &str) -> Result<(), OvhDnsUpdaterError> {
+        let record_description = format!(
+            "{} record \"{}\" of zone {}",
+            self.record_type, self.name, zone
+        );
+        match (&self.target, self.use_public_ip) {
+            (Some(_), true) => Err(InvalidZoneStateFile(format!(
+                "{} has both a target and use_public_ip",
+                record_description
+            ))),
+            (None, false) => Err(InvalidZoneStateFile(format!(
+                "{} has no target",
+                record_description
+            ))),
+            (None, true)
+                if self.record_type != DnsRecordType::A
+                    && self.record_type != DnsRecordType::Aaaa =>
+            {
+                Err(InvalidZoneStateFile(format!(
+                    "{} can't use a public IP address, only A and AAAA records can",
+                    record_description
+                )))
+            }
+            (Some(target), false) => self.record_type.validate_target(target).map_err(|reason| {
+                InvalidZoneStateFile(format!("{}: {}", record_description, reason))
+            }),
+            (None, true) => Ok(()),
+        }
+    }
+}
+
+impl ZoneState {
+    /// Build the desired records of the zone, replacing `use_public_ip` with the given addresses
+    /// # Arguments
+    /// * `public_ipv4`, `public_ipv6` - The current public addresses, only needed if a record uses them
+    /// # Returns
+    /// * `Ok(Vec<(DnsRecord, Option<u32>)>)` - Each desired record with its TTL, None if the existing TTL is kept
+    /// * `Err(OvhDnsUpdaterError)` - If a needed address is missing or a target is invalid
+    pub fn desired_records(
+        &self,
+        public_ipv4: Option<Ipv4Addr>,
+        public_ipv6: Option<Ipv6Addr>,
+    ) -> Result<Vec<(DnsRecord, Option<u32>)>, OvhDnsUpdaterError> {
+        self.records
+            .iter()
+            .map(|desired_record| {
+                let target = match (&desired_record.target, desired_record.record_type) {
+                    (Some(target), _) => target.clone(),
+                    (None, DnsRecordType::A) => public_ipv4
+                        .ok_or_else(|| {
+                            InvalidZoneStateFile("the public IPv4 address is unknown".to_string())
+                        })?
+                        .to_string(),
+                    (None, _) => public_ipv6
+                        .ok_or_else(|| {
+                            InvalidZoneStateFile("the public IPv6 address is unknown".to_string())
+                        })?
+                        .to_string(),
+                };
+                let dns_record = DnsRecord::new(
+                    &self.name,
+                    desired_record.sub_domain(),
+                    desired_record.record_type,
+                    &target,
+                    desired_record.ttl.unwrap_or(0),
+                )?;
+                Ok((dns_record, desired_record.ttl))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZONE_STATE_FILE: &str = r#"
+        [[zone]]
+        name = "example.com"
+        prune = true
+
+        [[zone.record]]
+        name = "home"
+        type = "A"
+        ttl = 300
+        use_public_ip = true
+
+        [[zone.record]]
+        name = "@"
+        type = "MX"
+        target = "10 mx1.example.com."
+    "#;
+
+    #[test]
+    fn test_from_toml() {
+        let zone_state_file = ZoneStateFile::from_toml(ZONE_STATE_FILE).unwrap();
+        assert_eq!(zone_state_file.zones.len(), 1);
+        assert!(zone_state_file.zones[0].prune);
+        assert_eq!(zone_state_file.zones[0].records[1].sub_domain(), "");
+        assert!(zone_state_file.needs_public_ipv4());
+        assert!(!zone_state_file.needs_public_ipv6());
+
+        let missing_target = "[[zone]]\nname = \"example.com\"\n[[zone.record]]\ntype = \"TXT\"";
+        assert!(matches!(
+            ZoneStateFile::from_toml(missing_target),
+            Err(InvalidZoneStateFile(_))
+        ));
+        let public_ip_cname = "[[zone]]\nname = \"example.com\"\n[[zone.record]]\ntype = \"CNAME\"\nuse_public_ip = true";
+        assert!(matches!(
+            ZoneStateFile::from_toml(public_ip_cname),
+            Err(InvalidZoneStateFile(_))
+        ));
+        let invalid_mx =
+            "[[zone]]\nname = \"example.com\"\n[[zone.record]]\ntype = \"MX\"\ntarget = \"mx1\"";
+        assert!(matches!(
+            ZoneStateFile::from_toml(invalid_mx),
+            Err(InvalidZoneStateFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_desired_records() {
+        let zone_state_file = ZoneStateFile::from_toml(ZONE_STATE_FILE).unwrap();
+        let zone = &zone_state_file.zones[0];
+        let desired_records = zone
+            .desired_records(Some(Ipv4Addr::new(1, 2, 3, 4)), None)
+            .unwrap();
+        assert_eq!(desired_records[0].0.target, "1.2.3.4");
+        assert_eq!(desired_records[0].1, Some(300));
+        assert_eq!(desired_records[1].0.full_domain(), "example.com");
+        assert_eq!(desired_records[1].1, None);
+        assert!(zone.desired_records(None, None).is_err());
+    }
+}
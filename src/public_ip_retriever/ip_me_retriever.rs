@@ -4,6 +4,7 @@ use crate::public_ip_retriever::generic_ip_types::{GenericIpTypes, Ip4Types, Ip6
 use crate::public_ip_retriever::public_ip_retriever_error::PublicIpRetrieverError;
 use crate::public_ip_retriever::PublicIpRetriever;
 use async_trait::async_trait;
+use log::trace;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use thiserror::Error;
 
@@ -33,10 +34,17 @@ impl IpMeIpRetriever {
     ) -> Result<GenericIPTypesStruct::GenericIpAddr, PublicIpRetrieverError> {
         let ip_response_result = self.client.get(ip_me_service_url).send().await;
         let fields_coma_str = ip_response_result
-            .map_err(|_| GenericIPTypesStruct::IP_CONNECTION_ERROR)?
+            .map_err(|e| {
+                trace!("GET {} failed: {}", ip_me_service_url, e);
+                GenericIPTypesStruct::IP_CONNECTION_ERROR
+            })?
             .text()
             .await
-            .map_err(|_| GenericIPTypesStruct::IP_PARSE_ERROR)?;
+            .map_err(|e| {
+                trace!("GET {} answer unreadable: {}", ip_me_service_url, e);
+                GenericIPTypesStruct::IP_PARSE_ERROR
+            })?;
+        trace!("GET {} answered {:?}", ip_me_service_url, fields_coma_str);
         let ip_str = Self::parse_coma_separated_fields(&fields_coma_str)
             .map_err(|_| GenericIPTypesStruct::IP_PARSE_ERROR)?;
         GenericIPTypesStruct::IP_STR_PARSER(ip_str)
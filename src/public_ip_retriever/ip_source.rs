@@ -0,0 +1,81 @@
+//! Choice of the service used to retrieve the public IP address
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Service asked for the public IP address
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IpSource {
+    /// Ask all the services at once and keep the first answer
+    #[default]
+    Fastest,
+    /// https://ip4.me and https://ip6only.me
+    IpMe,
+    /// https://ipify.org
+    Ipify,
+    /// https://lafibre.info
+    LaFibreInfo,
+    /// https://ident.me
+    IdentMe,
+}
+
+impl IpSource {
+    const FASTEST_STR: &'static str = "fastest";
+    const IP_ME_STR: &'static str = "ip-me";
+    const IPIFY_STR: &'static str = "ipify";
+    const LAFIBRE_INFO_STR: &'static str = "lafibre-info";
+    const IDENT_ME_STR: &'static str = "ident-me";
+}
+
+impl Display for IpSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IpSource::Fastest => Self::FASTEST_STR,
+            IpSource::IpMe => Self::IP_ME_STR,
+            IpSource::Ipify => Self::IPIFY_STR,
+            IpSource::LaFibreInfo => Self::LAFIBRE_INFO_STR,
+            IpSource::IdentMe => Self::IDENT_ME_STR,
+        })
+    }
+}
+
+impl FromStr for IpSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Self::FASTEST_STR => Ok(IpSource::Fastest),
+            Self::IP_ME_STR => Ok(IpSource::IpMe),
+            Self::IPIFY_STR => Ok(IpSource::Ipify),
+            Self::LAFIBRE_INFO_STR => Ok(IpSource::LaFibreInfo),
+            Self::IDENT_ME_STR => Ok(IpSource::IdentMe),
+            _ => Err(format!(
+                "expected \"{}\", \"{}\", \"{}\", \"{}\" or \"{}\"",
+                Self::FASTEST_STR,
+                Self::IP_ME_STR,
+                Self::IPIFY_STR,
+                Self::LAFIBRE_INFO_STR,
+                Self::IDENT_ME_STR
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_source_from_str() {
+        for ip_source in [
+            IpSource::Fastest,
+            IpSource::IpMe,
+            IpSource::Ipify,
+            IpSource::LaFibreInfo,
+            IpSource::IdentMe,
+        ] {
+            assert_eq!(ip_source.to_string().parse::<IpSource>(), Ok(ip_source));
+        }
+        assert!("ip.me".parse::<IpSource>().is_err());
+    }
+}
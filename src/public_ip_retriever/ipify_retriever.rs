@@ -4,6 +4,7 @@ use crate::public_ip_retriever::generic_ip_types::{GenericIpTypes, Ip4Types, Ip6
 use crate::public_ip_retriever::public_ip_retriever_error::PublicIpRetrieverError;
 use crate::public_ip_retriever::PublicIpRetriever;
 use async_trait::async_trait;
+use log::trace;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 pub(crate) struct IpifyIpRetriever {
@@ -23,11 +24,20 @@ impl IpifyIpRetriever {
             Ok(ip_response) => {
                 let ip_obj_result = ip_response.json::<IpifyResponse>().await;
                 match ip_obj_result {
-                    Ok(ip_obj) => GenericIPTypesStruct::IP_STR_PARSER(&ip_obj.ip),
-                    Err(_) => Err(GenericIPTypesStruct::IP_PARSE_ERROR),
+                    Ok(ip_obj) => {
+                        trace!("GET {} answered {:?}", ip_me_service_url, ip_obj.ip);
+                        GenericIPTypesStruct::IP_STR_PARSER(&ip_obj.ip)
+                    }
+                    Err(e) => {
+                        trace!("GET {} answer unreadable: {}", ip_me_service_url, e);
+                        Err(GenericIPTypesStruct::IP_PARSE_ERROR)
+                    }
                 }
             }
-            Err(_) => Err(GenericIPTypesStruct::IP_CONNECTION_ERROR),
+            Err(e) => {
+                trace!("GET {} failed: {}", ip_me_service_url, e);
+                Err(GenericIPTypesStruct::IP_CONNECTION_ERROR)
+            }
         }
     }
 }
@@ -4,6 +4,7 @@ use crate::public_ip_retriever::generic_ip_types::{GenericIpTypes, Ip4Types, Ip6
 use crate::public_ip_retriever::public_ip_retriever_error::PublicIpRetrieverError;
 use crate::public_ip_retriever::PublicIpRetriever;
 use async_trait::async_trait;
+use log::trace;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 pub(crate) struct LaFibreInfoIpRetriever {
@@ -23,11 +24,20 @@ impl LaFibreInfoIpRetriever {
             Ok(ip_response) => {
                 let ip_result = ip_response.text().await;
                 match ip_result {
-                    Ok(ip_str) => GenericIPTypesStruct::IP_STR_PARSER(&ip_str),
-                    Err(_) => Err(GenericIPTypesStruct::IP_PARSE_ERROR),
+                    Ok(ip_str) => {
+                        trace!("GET {} answered {:?}", ip_me_service_url, ip_str);
+                        GenericIPTypesStruct::IP_STR_PARSER(&ip_str)
+                    }
+                    Err(e) => {
+                        trace!("GET {} answer unreadable: {}", ip_me_service_url, e);
+                        Err(GenericIPTypesStruct::IP_PARSE_ERROR)
+                    }
                 }
             }
-            Err(_) => Err(GenericIPTypesStruct::IP_CONNECTION_ERROR),
+            Err(e) => {
+                trace!("GET {} failed: {}", ip_me_service_url, e);
+                Err(GenericIPTypesStruct::IP_CONNECTION_ERROR)
+            }
         }
     }
 }
@@ -1,10 +1,14 @@
+use crate::public_ip_retriever::ip_source::IpSource;
 use crate::public_ip_retriever::public_ip_retriever_error::PublicIpRetrieverError;
 use async_trait::async_trait;
 use futures::future;
+use log::debug;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Instant;
 
 mod check_ip_format;
 mod generic_ip_types;
+pub mod ip_source;
 mod public_ip_retriever_error;
 
 // Other retrievers can be added here
@@ -27,6 +31,12 @@ pub struct IpRetrieverFromFasterService {
     ident_me_retriever: ident_me_retriever::IdentMeIpRetriever,
 }
 
+impl Default for IpRetrieverFromFasterService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IpRetrieverFromFasterService {
     pub fn new() -> Self {
         Self {
@@ -37,45 +47,98 @@ impl IpRetrieverFromFasterService {
         }
     }
 
+    /// Services asked at once with `IpSource::Fastest`
+    const SERVICES: [IpSource; 4] = [
+        IpSource::IpMe,
+        IpSource::Ipify,
+        IpSource::LaFibreInfo,
+        IpSource::IdentMe,
+    ];
+
     pub async fn get_ip4(&self) -> Option<Ipv4Addr> {
-        let ip_me_ip4_future = self.ip_me_retriever.get_ip4();
-        let ipify_ip4_future = self.ipify_retriever.get_ip4();
-        let lafibre_info_ip4_future = self.lafibre_info_retriever.get_ip4();
-        let ident_me_ip4_future = self.ident_me_retriever.get_ip4();
+        self.get_ip4_from(IpSource::Fastest)
+            .await
+            .map(|(ip4, _)| ip4)
+    }
 
-        let ip4_future_select_result = future::select_ok(vec![
-            ip_me_ip4_future,
-            ipify_ip4_future,
-            lafibre_info_ip4_future,
-            ident_me_ip4_future,
-        ])
-        .await;
+    pub async fn get_ip6(&self) -> Option<Ipv6Addr> {
+        self.get_ip6_from(IpSource::Fastest)
+            .await
+            .map(|(ip6, _)| ip6)
+    }
 
-        match ip4_future_select_result {
-            Ok(ip4_ok_tuple) => Some(ip4_ok_tuple.0),
+    /// Returns the public IPv4 address given by the chosen service, with the service that answered,
+    /// i.e. the first one to answer with `IpSource::Fastest`. None if it can't be retrieved
+    pub async fn get_ip4_from(&self, ip_source: IpSource) -> Option<(Ipv4Addr, IpSource)> {
+        let ip4_futures = Self::services(ip_source).into_iter().map(|service| {
+            Box::pin(async move {
+                let start = Instant::now();
+                let ip4 = match service {
+                    IpSource::IpMe => self.ip_me_retriever.get_ip4().await,
+                    IpSource::Ipify => self.ipify_retriever.get_ip4().await,
+                    IpSource::LaFibreInfo => self.lafibre_info_retriever.get_ip4().await,
+                    IpSource::IdentMe => self.ident_me_retriever.get_ip4().await,
+                    IpSource::Fastest => unreachable!("fastest is not a service"),
+                };
+                log_service_answer(service, &ip4, start);
+                ip4.map(|ip4| (ip4, service))
+            })
+        });
+        match future::select_ok(ip4_futures).await {
+            Ok(((ip4, service), _)) => {
+                debug!("Public IPv4 address {} given by {}", ip4, service);
+                Some((ip4, service))
+            }
             Err(_) => None,
         }
     }
 
-    pub async fn get_ip6(&self) -> Option<Ipv6Addr> {
-        let ip_me_ip6_future = self.ip_me_retriever.get_ip6();
-        let ipify_ip6_future = self.ipify_retriever.get_ip6();
-        let lafibre_info_ip6_future = self.lafibre_info_retriever.get_ip6();
-        let ident_me_ip6_future = self.ident_me_retriever.get_ip6();
-
-        let ip6_future_select_result = future::select_ok(vec![
-            ip_me_ip6_future,
-            ipify_ip6_future,
-            lafibre_info_ip6_future,
-            ident_me_ip6_future,
-        ])
-        .await;
-
-        match ip6_future_select_result {
-            Ok(ip6_ok_tuple) => Some(ip6_ok_tuple.0),
+    /// Returns the public IPv6 address given by the chosen service, with the service that answered,
+    /// i.e. the first one to answer with `IpSource::Fastest`. None if it can't be retrieved
+    pub async fn get_ip6_from(&self, ip_source: IpSource) -> Option<(Ipv6Addr, IpSource)> {
+        let ip6_futures = Self::services(ip_source).into_iter().map(|service| {
+            Box::pin(async move {
+                let start = Instant::now();
+                let ip6 = match service {
+                    IpSource::IpMe => self.ip_me_retriever.get_ip6().await,
+                    IpSource::Ipify => self.ipify_retriever.get_ip6().await,
+                    IpSource::LaFibreInfo => self.lafibre_info_retriever.get_ip6().await,
+                    IpSource::IdentMe => self.ident_me_retriever.get_ip6().await,
+                    IpSource::Fastest => unreachable!("fastest is not a service"),
+                };
+                log_service_answer(service, &ip6, start);
+                ip6.map(|ip6| (ip6, service))
+            })
+        });
+        match future::select_ok(ip6_futures).await {
+            Ok(((ip6, service), _)) => {
+                debug!("Public IPv6 address {} given by {}", ip6, service);
+                Some((ip6, service))
+            }
             Err(_) => None,
         }
     }
+
+    /// The services to ask for the given source
+    fn services(ip_source: IpSource) -> Vec<IpSource> {
+        match ip_source {
+            IpSource::Fastest => Self::SERVICES.to_vec(),
+            service => vec![service],
+        }
+    }
+}
+
+/// Logs the address or the error given by a service, with the time it took
+fn log_service_answer<IpAddr: std::fmt::Display>(
+    service: IpSource,
+    answer: &Result<IpAddr, PublicIpRetrieverError>,
+    start: Instant,
+) {
+    let elapsed_ms = start.elapsed().as_millis();
+    match answer {
+        Ok(ip) => debug!("{} answered {} in {} ms", service, ip, elapsed_ms),
+        Err(e) => debug!("{} failed after {} ms: {}", service, elapsed_ms, e),
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum PublicIpRetrieverError {
     #[error("Failed to establish IPv4 connection")]
     Ip4ConnectionError,